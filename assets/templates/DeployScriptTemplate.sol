@@ -0,0 +1,18 @@
+// SPDX-License-Identifier: UNLICENSED
+pragma solidity ^0.8.13;
+
+import {Script} from "forge-std/Script.sol";
+{imports}
+
+contract Deploy{router_name} is Script {
+{constants}
+    function run() external returns ({router_name} router) {
+        vm.startBroadcast();
+
+{deployments}
+
+        router = {router_deployment};
+
+        vm.stopBroadcast();
+    }
+{deploy_module}}
@@ -0,0 +1,72 @@
+// SPDX-License-Identifier: UNLICENSED
+pragma solidity ^0.8.13;
+
+{imports}
+{interface}
+
+contract {router_name} {
+{modules}
+
+    struct Modules {
+{struct}
+    }
+
+    constructor(Modules memory $) {
+{immutables}
+    }
+
+{unknown_selector_error}
+
+    function findImplementationAddress(bytes32 implementation) internal view returns (address result) {
+{resolver}
+    }
+
+{feature_admin}
+    fallback() external payable {
+        // Lookup table: Function selector => implementation contract
+        bytes4 sig4 = msg.sig;
+        bytes32 implementation;
+        bool isReadOnly;
+
+        assembly {
+            let sig32 := shr(224, sig4)
+
+{checks}
+            function findImplementation(sig) -> result, readOnly {
+{selectors}
+            }
+
+            implementation, isReadOnly := findImplementation(sig32)
+        }
+
+        address implementation_address = findImplementationAddress(implementation);
+
+        if (implementation_address == address(0)) {
+{unknown_selector}
+        }
+
+{hooks}
+        // Forward the call to the implementation contract
+        assembly {
+            calldatacopy(0, 0, calldatasize())
+
+            let result
+            switch isReadOnly
+            case 0 {
+                result := call(gas(), implementation_address, callvalue(), 0, calldatasize(), 0, 0)
+            }
+            default {
+                result := staticcall(gas(), implementation_address, 0, calldatasize(), 0, 0)
+            }
+            returndatacopy(0, 0, returndatasize())
+
+            switch result
+            case 0 {
+                revert(0, returndatasize())
+            }
+            default {
+                return(0, returndatasize())
+            }
+        }
+    }
+}
@@ -0,0 +1,28 @@
+    // ERC-7201 namespace `{features_namespace}`: mapping(bytes32 feature => bool enabled)
+    bytes32 internal constant FEATURES_SLOT = {features_slot};
+
+    // Features, identified by keccak256(name):
+{feature_ids}
+
+    event FeatureToggled(bytes32 indexed feature, bool enabled);
+
+    function setFeatureEnabled(bytes32 feature, bool enabled) external {
+{admin_check}
+
+        assembly {
+            mstore(0, feature)
+            mstore(32, FEATURES_SLOT)
+            sstore(keccak256(0, 64), enabled)
+        }
+
+        emit FeatureToggled(feature, enabled);
+    }
+
+    function isFeatureEnabled(bytes32 feature) external view returns (bool enabled) {
+        assembly {
+            mstore(0, feature)
+            mstore(32, FEATURES_SLOT)
+            enabled := sload(keccak256(0, 64))
+        }
+    }
+
@@ -1,6 +1,7 @@
 // SPDX-License-Identifier: UNLICENSED
 pragma solidity ^0.8.13;
 
+{imports}
 {interface}
 
 contract {router_name} {
@@ -14,12 +15,13 @@ contract {router_name} {
 {immutables}
     }
 
-    error UnknownSelector(bytes4 sel);
+{unknown_selector_error}
 
     function findImplementationAddress(bytes32 implementation) internal view returns (address result) {
 {resolver}
     }
 
+{feature_admin}
     fallback() external payable {
         // Lookup table: Function selector => implementation contract
         bytes4 sig4 = msg.sig;
@@ -28,6 +30,7 @@ contract {router_name} {
         assembly {
             let sig32 := shr(224, sig4)
 
+{checks}
             function findImplementation(sig) -> result {
 {selectors}
             }
@@ -38,9 +41,10 @@ contract {router_name} {
         address implementation_address = findImplementationAddress(implementation);
 
         if (implementation_address == address(0)) {
-            revert UnknownSelector(sig4);
+{unknown_selector}
         }
 
+{hooks}
         // Delegatecall to the implementation contract
         assembly {
             calldatacopy(0, 0, calldatasize())
@@ -0,0 +1,21 @@
+    bytes32 private constant CACHE_NAMESPACE = keccak256("{router_name}.registry.cache");
+
+    /// Transient writes revert in a static context, lookups of view and pure functions, which
+    /// may be reached through `staticcall`, read the cache but never fill it.
+    function findImplementationAddress(bytes32 implementation, bool readOnly) internal returns (address result) {
+        bytes32 slot = keccak256(abi.encode(CACHE_NAMESPACE, implementation));
+
+        assembly {
+            result := tload(slot)
+        }
+
+        if (result == address(0)) {
+            result = IModuleRegistry(REGISTRY).getModule(implementation);
+
+            if (!readOnly) {
+                assembly {
+                    tstore(slot, result)
+                }
+            }
+        }
+    }
@@ -0,0 +1,3 @@
+    function findImplementationAddress(bytes32 implementation) internal view returns (address result) {
+        result = IModuleRegistry(REGISTRY).getModule(implementation);
+    }
@@ -0,0 +1,71 @@
+// SPDX-License-Identifier: UNLICENSED
+pragma solidity {pragma};
+
+{imports}
+{interface}
+
+interface IModuleRegistry {
+    function getModule(bytes32 id) external view returns (address);
+}
+
+contract {router_name} {
+{modules}
+
+    address public immutable REGISTRY;
+
+    constructor(address registry) {
+        REGISTRY = registry;
+    }
+
+{unknown_selector_error}
+    error ModuleNotRegistered(bytes32 id);
+
+{resolver}
+{feature_admin}
+    fallback() external payable {
+        // Lookup table: Function selector => module identifier
+        bytes4 sig4 = msg.sig;
+        bytes32 implementation;
+{read_only_declaration}
+
+        assembly {
+            let sig32 := shr(224, sig4)
+
+{checks}
+            function findImplementation(sig) -> result{read_only} {
+{selectors}
+            }
+
+            implementation{read_only} := findImplementation(sig32)
+        }
+
+        address implementation_address;
+
+        if (implementation == bytes32(0)) {
+{unknown_selector}
+        } else {
+            implementation_address = findImplementationAddress(implementation{read_only});
+
+            if (implementation_address == address(0)) {
+                revert ModuleNotRegistered(implementation);
+            }
+        }
+
+{hooks}
+        // Delegatecall to the implementation contract
+        assembly {
+            calldatacopy(0, 0, calldatasize())
+
+            let result := delegatecall(gas(), implementation_address, 0, calldatasize(), 0, 0)
+            returndatacopy(0, 0, returndatasize())
+
+            switch result
+            case 0 {
+                revert(0, returndatasize())
+            }
+            default {
+                return(0, returndatasize())
+            }
+        }
+    }
+}
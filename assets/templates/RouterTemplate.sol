@@ -1,13 +1,15 @@
 // SPDX-License-Identifier: UNLICENSED
 pragma solidity ^0.8.13;
 
+{imports}
 {interface}
 
 contract {router_name} {
 {modules}
 
-    error UnknownSelector(bytes4 sel);
+{unknown_selector_error}
 
+{feature_admin}
     fallback() external payable {
         // Lookup table: Function selector => implementation contract
         bytes4 sig4 = msg.sig;
@@ -16,6 +18,7 @@ contract {router_name} {
         assembly {
             let sig32 := shr(224, sig4)
 
+{checks}
             function findImplementation(sig) -> result {
 {selectors}
             }
@@ -24,9 +27,10 @@ contract {router_name} {
         }
 
         if (implementation == address(0)) {
-            revert UnknownSelector(sig4);
+{unknown_selector}
         }
 
+{hooks}
         // Delegatecall to the implementation contract
         assembly {
             calldatacopy(0, 0, calldatasize())
@@ -0,0 +1,174 @@
+// SPDX-License-Identifier: UNLICENSED
+pragma solidity ^0.8.13;
+
+{imports}
+{interface}
+
+contract {router_name} {
+{modules}
+
+    bytes32 internal constant IMPLEMENTATION_SLOT = {implementation_slot};
+    bytes32 internal constant ADMIN_SLOT = {admin_slot};
+
+    address private immutable __self = address(this);
+
+    event Upgraded(address indexed implementation);
+    event AdminChanged(address previousAdmin, address newAdmin);
+
+{unknown_selector_error}
+    error Unauthorized(address sender);
+    error UnauthorizedCallContext();
+    error UnsupportedProxiableUUID(address implementation);
+    error InvalidAdmin(address admin);
+
+    function proxiableUUID() external view returns (bytes32) {
+        // Must be called on the implementation, not through the proxy
+        if (address(this) != __self) {
+            revert UnauthorizedCallContext();
+        }
+
+        return IMPLEMENTATION_SLOT;
+    }
+
+    function changeAdmin(address newAdmin) external {
+        address admin = _checkAdmin();
+
+        if (newAdmin == address(0)) {
+            revert InvalidAdmin(newAdmin);
+        }
+
+        assembly {
+            sstore(ADMIN_SLOT, newAdmin)
+        }
+
+        emit AdminChanged(admin, newAdmin);
+    }
+
+    function upgradeToAndCall(address newImplementation, bytes calldata data) external payable {
+        _checkAdmin();
+
+        (bool success, bytes memory uuid) =
+            newImplementation.staticcall(abi.encodeWithSelector(this.proxiableUUID.selector));
+
+        if (!success || uuid.length != 32 || abi.decode(uuid, (bytes32)) != IMPLEMENTATION_SLOT) {
+            revert UnsupportedProxiableUUID(newImplementation);
+        }
+
+        assembly {
+            sstore(IMPLEMENTATION_SLOT, newImplementation)
+        }
+
+        emit Upgraded(newImplementation);
+
+        if (data.length > 0) {
+            (bool ok, bytes memory result) = newImplementation.delegatecall(data);
+            if (!ok) {
+                assembly {
+                    revert(add(result, 32), mload(result))
+                }
+            }
+        }
+    }
+
+    function _checkAdmin() private view returns (address admin) {
+        // Must be called through the proxy
+        if (address(this) == __self) {
+            revert UnauthorizedCallContext();
+        }
+
+        assembly {
+            admin := sload(ADMIN_SLOT)
+        }
+
+        if (msg.sender != admin) {
+            revert Unauthorized(msg.sender);
+        }
+    }
+
+{feature_admin}
+    fallback() external payable {
+        // Lookup table: Function selector => implementation contract
+        bytes4 sig4 = msg.sig;
+        address implementation;
+
+        assembly {
+            let sig32 := shr(224, sig4)
+
+{checks}
+            function findImplementation(sig) -> result {
+{selectors}
+            }
+
+            implementation := findImplementation(sig32)
+        }
+
+        if (implementation == address(0)) {
+{unknown_selector}
+        }
+
+{hooks}
+        // Delegatecall to the implementation contract
+        assembly {
+            calldatacopy(0, 0, calldatasize())
+
+            let result := delegatecall(gas(), implementation, 0, calldatasize(), 0, 0)
+            returndatacopy(0, 0, returndatasize())
+
+            switch result
+            case 0 {
+                revert(0, returndatasize())
+            }
+            default {
+                return(0, returndatasize())
+            }
+        }
+    }
+}
+
+/// Minimal ERC-1967 proxy. Upgrades and admin transfers are implemented by the router (UUPS),
+/// so they keep working as long as every new implementation exposes them.
+contract {router_name}Proxy {
+    bytes32 internal constant IMPLEMENTATION_SLOT = {implementation_slot};
+    bytes32 internal constant ADMIN_SLOT = {admin_slot};
+
+    event Upgraded(address indexed implementation);
+    event AdminChanged(address previousAdmin, address newAdmin);
+
+    constructor(address implementation, address admin, bytes memory data) payable {
+        assembly {
+            sstore(IMPLEMENTATION_SLOT, implementation)
+            sstore(ADMIN_SLOT, admin)
+        }
+
+        emit Upgraded(implementation);
+        emit AdminChanged(address(0), admin);
+
+        if (data.length > 0) {
+            (bool ok, bytes memory result) = implementation.delegatecall(data);
+            if (!ok) {
+                assembly {
+                    revert(add(result, 32), mload(result))
+                }
+            }
+        }
+    }
+
+    fallback() external payable {
+        assembly {
+            let implementation := sload(IMPLEMENTATION_SLOT)
+
+            calldatacopy(0, 0, calldatasize())
+
+            let result := delegatecall(gas(), implementation, 0, calldatasize(), 0, 0)
+            returndatacopy(0, 0, returndatasize())
+
+            switch result
+            case 0 {
+                revert(0, returndatasize())
+            }
+            default {
+                return(0, returndatasize())
+            }
+        }
+    }
+}
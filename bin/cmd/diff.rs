@@ -0,0 +1,290 @@
+use std::{
+    collections::BTreeMap,
+    env, fs,
+    path::{Path, PathBuf},
+    process::{self, Command},
+};
+
+use alloy_primitives::hex::ToHexExt;
+use cannon_rs::generated::routers::{
+    diff::{diff_routers, surface_from_info, surfaces_from_json, Change, RoutedFunction, Surface},
+    inspect::inspect_router,
+    pipeline::{compile, Flavor, RouterSpec},
+    toml::TomlDefintion,
+};
+use clap::Parser;
+use eyre::Result;
+use foundry_cli::{opts::CoreBuildArgs, utils::LoadConfig};
+use foundry_config::{
+    figment::{
+        value::{Dict, Map},
+        Metadata, Profile, Provider,
+    },
+    Config,
+};
+use itertools::Itertools;
+use serde::Serialize;
+use yansi::Paint;
+
+use crate::cmd::generate::{create_project, prepare_specs, OutputArgs};
+
+// Loads project's figment and merges the build cli arguments into it
+foundry_config::merge_impl_figment_convert!(DiffArgs, opts);
+
+/// CLI arguments for `cannon-rs diff`.
+#[derive(Clone, Debug, Default, Serialize, Parser)]
+pub struct DiffArgs {
+    /// The previous version: a router TOML, a `<REVISION>:<PATH>` git object, or a JSON report,
+    /// contract artifact or ABI previously exported.
+    old: String,
+
+    /// The new version, in the same forms as the previous one.
+    new: String,
+
+    /// Only compare the given router.
+    #[clap(long, value_name = "ROUTER_NAME")]
+    router: Option<String>,
+
+    /// Exit with an error when selectors were removed or changed signature.
+    #[clap(long)]
+    fail_on_breaking: bool,
+
+    /// Print the changes as JSON.
+    #[clap(long)]
+    json: bool,
+
+    #[command(flatten)]
+    opts: CoreBuildArgs,
+}
+
+impl DiffArgs {
+    pub fn run(self) -> Result<()> {
+        let old = self.load(&self.old)?;
+        let new = self.load(&self.new)?;
+
+        let diffs = diff_routers(&old, &new)
+            .into_iter()
+            .filter(|(name, _)| self.router.as_ref().map_or(true, |router| router == name))
+            .collect::<BTreeMap<String, Vec<Change>>>();
+
+        if self.json {
+            println!("{}", serde_json::to_string_pretty(&diffs)?);
+        } else {
+            for (name, changes) in &diffs {
+                print_changes(name, changes);
+            }
+        }
+
+        let breaking = diffs.values().flatten().filter(|c| c.is_breaking()).count();
+        if self.fail_on_breaking && breaking > 0 {
+            eyre::bail!("{breaking} breaking change(s) in the routed surface");
+        }
+
+        Ok(())
+    }
+
+    /// Load the router surfaces of one side of the diff.
+    fn load(&self, input: &str) -> Result<BTreeMap<String, Surface>> {
+        if Path::new(input).is_file() {
+            return match input.ends_with(".json") {
+                true => surfaces_from_json(&fs::read_to_string(input)?),
+                false => self.toml_surfaces(self.try_load_config_emit_warnings()?, input.into()),
+            };
+        }
+
+        let (rev, path) = input.split_once(':').ok_or_else(|| {
+            eyre::eyre!("`{input}` is neither a file nor a `<REVISION>:<PATH>` git object")
+        })?;
+
+        let config = self.try_load_config_emit_warnings()?;
+        let project = config.create_project(false, true)?;
+
+        if path.ends_with(".json") {
+            return surfaces_from_json(&git_show(project.root(), rev, path)?);
+        }
+
+        let worktree = Worktree::new(project.root(), rev)?;
+        worktree.link_libraries(&project.paths.libraries)?;
+
+        let config = Config::load_with_root(&worktree.path);
+        self.toml_surfaces(config, worktree.path.join(path))
+    }
+
+    /// Compile the routers of a TOML file and read their surfaces.
+    fn toml_surfaces(&self, config: Config, toml: PathBuf) -> Result<BTreeMap<String, Surface>> {
+        let specs = TomlDefintion::from_path(toml)?
+            .sorted_routers()
+            .into_iter()
+            .map(|(name, router)| RouterSpec::new(name, router, Flavor::Immutable))
+            .collect::<Vec<RouterSpec>>();
+
+        let out = OutputArgs {
+            json: self.json,
+            ..Default::default()
+        };
+        let project = create_project(config, &out, &specs)?;
+        let specs = prepare_specs(&project, &out, specs)?;
+
+        let output = compile(&project, &specs)?;
+
+        specs
+            .iter()
+            .map(|spec| {
+                let info = inspect_router(
+                    &project,
+                    &output,
+                    spec.name.clone(),
+                    spec.router.modules.clone(),
+                    spec.flavor,
+                )?;
+                Ok((spec.name.clone(), surface_from_info(&info)))
+            })
+            .collect()
+    }
+}
+
+/// A detached git worktree of a revision, removed when dropped.
+struct Worktree {
+    repo: PathBuf,
+    path: PathBuf,
+}
+
+impl Worktree {
+    fn new(repo: &Path, rev: &str) -> Result<Self> {
+        let name = rev.replace(|c: char| !c.is_ascii_alphanumeric(), "-");
+        let path = env::temp_dir().join(format!("cannon-diff-{name}-{}", process::id()));
+
+        let status = Command::new("git")
+            .arg("-C")
+            .arg(repo)
+            .args(["worktree", "add", "--detach", "--quiet"])
+            .arg(&path)
+            .arg(rev)
+            .status()?;
+        if !status.success() {
+            eyre::bail!("Failed to check out `{rev}`");
+        }
+
+        Ok(Self {
+            repo: repo.to_path_buf(),
+            path,
+        })
+    }
+
+    /// Dependencies are not checked out in worktrees, point them to the current project's.
+    fn link_libraries(&self, libraries: &[PathBuf]) -> Result<()> {
+        for library in libraries {
+            let Ok(relative) = library.strip_prefix(&self.repo) else {
+                continue;
+            };
+            let target = self.path.join(relative);
+
+            if target.is_dir() && fs::read_dir(&target)?.next().is_some() {
+                continue;
+            }
+            if target.is_dir() {
+                fs::remove_dir(&target)?;
+            }
+            if let Some(parent) = target.parent() {
+                fs::create_dir_all(parent)?;
+            }
+
+            #[cfg(unix)]
+            std::os::unix::fs::symlink(library, &target)?;
+            #[cfg(windows)]
+            std::os::windows::fs::symlink_dir(library, &target)?;
+        }
+        Ok(())
+    }
+}
+
+impl Drop for Worktree {
+    fn drop(&mut self) {
+        let _ = Command::new("git")
+            .arg("-C")
+            .arg(&self.repo)
+            .args(["worktree", "remove", "--force"])
+            .arg(&self.path)
+            .status();
+    }
+}
+
+/// The content of a file at a git revision.
+fn git_show(repo: &Path, rev: &str, path: &str) -> Result<String> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(repo)
+        .arg("show")
+        .arg(format!("{rev}:{path}"))
+        .output()?;
+    if !output.status.success() {
+        eyre::bail!(
+            "Failed to read `{rev}:{path}`: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(String::from_utf8(output.stdout)?)
+}
+
+fn print_changes(name: &str, changes: &[Change]) {
+    let name = match name.is_empty() {
+        true => "ABI",
+        false => name,
+    };
+
+    if changes.is_empty() {
+        println!("{} unchanged", Paint::cyan(name).bold());
+        return;
+    }
+
+    println!("{}", Paint::cyan(name).bold());
+    for change in changes {
+        match change {
+            Change::Added { function } => {
+                println!("  {} {}", Paint::green("+"), describe(function))
+            }
+            Change::Removed { function } => {
+                println!("  {} {}", Paint::red("-"), describe(function))
+            }
+            Change::Moved { function, from } => println!(
+                "  {} {} moved from {from}",
+                Paint::yellow("~"),
+                describe(function)
+            ),
+            Change::SignatureChanged {
+                name,
+                removed,
+                added,
+            } => println!(
+                "  {} {name}: {} -> {}",
+                Paint::red("!"),
+                removed.iter().map(|f| &f.signature).join(", "),
+                added.iter().map(|f| &f.signature).join(", ")
+            ),
+        }
+    }
+    println!();
+}
+
+fn describe(function: &RoutedFunction) -> String {
+    let module = function
+        .module
+        .as_ref()
+        .map(|m| format!(" ({m})"))
+        .unwrap_or_default();
+    format!(
+        "{} {}{module}",
+        function.selector.encode_hex_with_prefix(),
+        function.signature
+    )
+}
+
+impl Provider for DiffArgs {
+    fn metadata(&self) -> Metadata {
+        Metadata::named("Diff Args Provider")
+    }
+
+    fn data(&self) -> Result<Map<Profile, Dict>, foundry_config::figment::Error> {
+        Ok(Map::from([(Config::selected_profile(), Dict::default())]))
+    }
+}
@@ -1,10 +1,22 @@
+use std::{
+    collections::{BTreeSet, HashMap, HashSet},
+    path::{Path, PathBuf},
+    sync::mpsc::{self, Receiver, RecvTimeoutError},
+    time::Duration,
+};
+
 use alloy_primitives::{Address, B256};
-use cannon_rs::generated::routers::deterministric::generate_router;
-use cannon_rs::generated::routers::identify_sources;
-use cannon_rs::generated::routers::toml::TomlDefintion;
-use clap::Parser;
+use cannon_rs::generated::routers::{
+    patterns::is_pattern,
+    pipeline::{Flavor, RouterSpec},
+    router_sources,
+    toml::Router,
+    unknown::UnknownSelector,
+};
+use clap::{Parser, ValueEnum};
 use eyre::Result;
 use foundry_cli::{opts::CoreBuildArgs, utils::LoadConfig};
+use foundry_compilers::Project;
 use foundry_config::{
     figment::{
         value::{Dict, Map},
@@ -12,10 +24,28 @@ use foundry_config::{
     },
     Config,
 };
+use notify::{Event, RecommendedWatcher, RecursiveMode, Watcher};
 use serde::Serialize;
 use yansi::Paint;
 
-use crate::cmd::generate::write_router;
+use crate::cmd::generate::{
+    create_project, emit_routers, generate, load_routers, prepare_specs, OutputArgs,
+};
+
+/// Quiet period after a change before regenerating, so a burst of writes regenerates once.
+const WATCH_DEBOUNCE: Duration = Duration::from_millis(200);
+
+/// Router output formats.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, ValueEnum)]
+pub enum RouterFormat {
+    /// Solidity router contract.
+    #[default]
+    Sol,
+    /// Standalone Yul object.
+    Yul,
+    /// Both the Solidity contract and the Yul object.
+    Both,
+}
 
 // Loads project's figment and merges the build cli arguments into it
 foundry_config::merge_impl_figment_convert!(GenerateRouterArgs, opts);
@@ -24,7 +54,7 @@ foundry_config::merge_impl_figment_convert!(GenerateRouterArgs, opts);
 #[derive(Clone, Debug, Default, Serialize, Parser)]
 pub struct GenerateRouterArgs {
     /// Generate from TOML configuration file.
-    #[clap(long, value_name = "ROUTER_TOML", conflicts_with_all = ["name", "module_names"])]
+    #[clap(long, value_name = "ROUTER_TOML", conflicts_with_all = ["name", "module_names", "unknown_selector"])]
     toml: Option<String>,
 
     /// Router name for router generation.
@@ -43,85 +73,212 @@ pub struct GenerateRouterArgs {
     /// Contract names for router generation.
     module_names: Vec<String>,
 
+    /// Behavior for unknown selectors: `revert`, `error:<SIGNATURE>`, `module:<MODULE>` or
+    /// `router:<ADDRESS>`.
+    #[clap(
+        long,
+        value_name = "BEHAVIOR",
+        default_value = "error:UnknownSelector(bytes4 sel)"
+    )]
+    unknown_selector: UnknownSelector,
+
+    /// Regenerate routers when their modules or the files they import change.
+    #[clap(long, conflicts_with_all = ["stdout", "json"])]
+    watch: bool,
+
+    /// Output format of the generated router.
+    #[clap(long, value_enum, default_value_t = RouterFormat::Sol)]
+    format: RouterFormat,
+
+    #[command(flatten)]
+    out: OutputArgs,
+
     #[command(flatten)]
     opts: CoreBuildArgs,
 }
 
 impl GenerateRouterArgs {
     pub fn run(self) -> Result<()> {
-        if self.toml.is_some() {
-            self.run_toml()?;
-            return Ok(());
-        }
-
         // Merge all configs.
         let config = self.try_load_config_emit_warnings()?;
 
-        let project = config.create_project(true, true)?;
-
-        let sources = identify_sources(&project, self.module_names.clone())?;
+        let routers = load_routers(
+            self.toml.as_ref(),
+            self.name.as_ref(),
+            Router::new(self.module_names.clone(), self.unknown_selector.clone()),
+        )?;
 
-        let output = project.compile_files(sources)?;
+        let specs = self.specs(routers);
 
-        if output.has_compiler_errors() {
-            println!("{output}");
-            eyre::bail!("Compilation failed");
+        if !self.watch {
+            return generate(config, &self.out, specs);
         }
 
-        generate_router(
-            &project,
-            &output,
-            self.name.unwrap().clone(),
-            self.module_names.clone(),
-            self.deployer,
-            self.salt,
-        )?;
-
-        Ok(())
+        let project = create_project(config, &self.out, &specs)?;
+        self.watch(&project, specs)
     }
 
-    fn run_toml(&self) -> Result<()> {
-        let config = self.try_load_config_emit_warnings()?;
+    /// One spec per router and requested format.
+    fn specs(&self, routers: Vec<(String, Router)>) -> Vec<RouterSpec> {
+        let (deployer, salt) = (self.deployer, self.salt);
 
-        let project = config.create_project(true, true)?;
+        let mut flavors = vec![];
+        if self.format != RouterFormat::Yul {
+            flavors.push(Flavor::Deterministic { deployer, salt });
+        }
+        if self.format != RouterFormat::Sol {
+            flavors.push(Flavor::DeterministicYul { deployer, salt });
+        }
+
+        routers
+            .into_iter()
+            .flat_map(|(name, router)| {
+                flavors
+                    .iter()
+                    .map(move |flavor| RouterSpec::new(name.clone(), router.clone(), *flavor))
+            })
+            .collect()
+    }
 
-        let toml = TomlDefintion::from_path(self.toml.clone().unwrap().into())?;
+    /// Regenerate routers whenever one of their sources, or a file they import, changes.
+    ///
+    /// Module patterns are expanded again on every change, picking up added or removed modules.
+    fn watch(&self, project: &Project, specs: Vec<RouterSpec>) -> Result<()> {
+        let mut expanded = prepare_specs(project, &self.out, specs.clone())?;
+        if let Err(err) = emit_routers(project, &self.out, &expanded) {
+            eprintln!("{} {err}", Paint::red("Error:"));
+        }
+
+        let (sender, events) = mpsc::channel();
+        let mut watcher = notify::recommended_watcher(sender)?;
 
-        let module_names: Vec<String> = toml
-            .routers
+        // New modules matching a pattern can appear anywhere in the sources.
+        let sources = specs
             .iter()
-            .flat_map(|(_, router)| router.modules.clone())
-            .collect();
+            .any(|spec| spec.router.modules.iter().any(|m| is_pattern(&m.contract)))
+            .then_some(project.paths.sources.as_path());
+        if let Some(sources) = sources {
+            watcher.watch(sources, RecursiveMode::Recursive)?;
+        }
+
+        let mut watched = watched_files(project, &expanded)?;
+        let mut directories = BTreeSet::new();
+        watch_directories(&mut watcher, &mut directories, sources, &watched)?;
+
+        println!(
+            "{} {} files for changes...",
+            Paint::cyan("Watching"),
+            watched.values().flatten().collect::<HashSet<_>>().len()
+        );
+
+        loop {
+            let changed_files = next_changes(&events)?;
+
+            let previous = match prepare_specs(project, &self.out, specs.clone()) {
+                Ok(specs) => std::mem::replace(&mut expanded, specs),
+                Err(err) => {
+                    eprintln!("{} {err}", Paint::red("Error:"));
+                    continue;
+                }
+            };
+
+            let affected = expanded
+                .iter()
+                .zip(&previous)
+                .filter(|(spec, before)| {
+                    spec.router.modules != before.router.modules
+                        || watched
+                            .get(&spec.name)
+                            .is_some_and(|files| files.iter().any(|f| changed_files.contains(f)))
+                })
+                .map(|(spec, _)| spec.clone())
+                .collect::<Vec<RouterSpec>>();
 
-        let sources = identify_sources(&project, module_names.clone())?;
+            if affected.is_empty() {
+                continue;
+            }
 
-        let output = project.compile_files(sources)?;
+            match emit_routers(project, &self.out, &affected) {
+                Ok(changed) if changed.is_empty() => {
+                    println!("{} routers changed", Paint::yellow("No"))
+                }
+                Ok(changed) => {
+                    for router_name in changed {
+                        println!("{} {router_name}", Paint::green("Regenerated"));
+                    }
+                }
+                Err(err) => eprintln!("{} {err}", Paint::red("Error:")),
+            }
 
-        if output.has_compiler_errors() {
-            println!("{output}");
-            eyre::bail!("Compilation failed");
+            // Imports may have been added or removed by the change.
+            if let Ok(files) = watched_files(project, &expanded) {
+                watched = files;
+                watch_directories(&mut watcher, &mut directories, sources, &watched)?;
+            }
         }
+    }
+}
+
+/// The files each router depends on.
+fn watched_files(
+    project: &Project,
+    specs: &[RouterSpec],
+) -> Result<HashMap<String, BTreeSet<PathBuf>>> {
+    specs
+        .iter()
+        .map(|spec| {
+            let files = router_sources(project, spec.router.contracts()?)?;
+            Ok((spec.name.clone(), files))
+        })
+        .collect()
+}
+
+/// Watch the directories of the watched files rather than the files, which editors often
+/// replace instead of writing in place. Directories under the recursively watched sources are
+/// skipped.
+fn watch_directories(
+    watcher: &mut RecommendedWatcher,
+    directories: &mut BTreeSet<PathBuf>,
+    sources: Option<&Path>,
+    watched: &HashMap<String, BTreeSet<PathBuf>>,
+) -> Result<()> {
+    let current = watched
+        .values()
+        .flatten()
+        .filter_map(|file| file.parent())
+        .filter(|dir| sources.map_or(true, |sources| !dir.starts_with(sources)))
+        .map(Path::to_path_buf)
+        .collect::<BTreeSet<PathBuf>>();
+
+    for dir in directories.difference(&current) {
+        watcher.unwatch(dir)?;
+    }
+    for dir in current.difference(directories) {
+        watcher.watch(dir, RecursiveMode::NonRecursive)?;
+    }
+
+    *directories = current;
+    Ok(())
+}
+
+/// Wait for a change, then collect the changes following it until the debounce period passes
+/// without any.
+fn next_changes(events: &Receiver<notify::Result<Event>>) -> Result<HashSet<PathBuf>> {
+    let mut changed = HashSet::new();
+    let mut next = events.recv()?;
 
-        for (router_name, router) in toml.routers.iter() {
-            let source = generate_router(
-                &project,
-                &output,
-                router_name.to_string(),
-                router.modules.clone(),
-                self.deployer,
-                self.salt,
-            )?;
-
-            let router_file_path = write_router(&project, &source, router_name)?;
-
-            println!(
-                "{} router file: {}",
-                Paint::green("Generated"),
-                router_file_path
-            );
+    loop {
+        let event = next?;
+        if !event.kind.is_access() {
+            changed.extend(event.paths);
         }
 
-        Ok(())
+        next = match events.recv_timeout(WATCH_DEBOUNCE) {
+            Ok(event) => event,
+            Err(RecvTimeoutError::Timeout) if !changed.is_empty() => return Ok(changed),
+            Err(RecvTimeoutError::Timeout) => events.recv()?,
+            Err(err) => return Err(err.into()),
+        };
     }
 }
 
@@ -1,5 +1,7 @@
 use cannon_rs::generated::routers::{
-    identify_sources, immutable::generate_router, toml::TomlDefintion,
+    pipeline::{Flavor, RouterSpec},
+    toml::Router,
+    unknown::UnknownSelector,
 };
 use clap::Parser;
 use eyre::Result;
@@ -11,9 +13,8 @@ use foundry_config::{
     },
     Config,
 };
-use yansi::Paint;
 
-use crate::cmd::generate::write_router;
+use crate::cmd::generate::{generate, load_routers, OutputArgs};
 
 // Loads project's figment and merges the build cli arguments into it
 foundry_config::merge_impl_figment_convert!(GenerateImmutableRouterArgs, opts);
@@ -33,90 +34,40 @@ pub struct GenerateImmutableRouterArgs {
     #[clap(conflicts_with = "toml")]
     module_names: Vec<String>,
 
+    /// Behavior for unknown selectors: `revert`, `error:<SIGNATURE>`, `module:<MODULE>` or
+    /// `router:<ADDRESS>`.
+    #[clap(
+        long,
+        value_name = "BEHAVIOR",
+        default_value = "error:UnknownSelector(bytes4 sel)",
+        conflicts_with = "toml"
+    )]
+    unknown_selector: UnknownSelector,
+
+    #[command(flatten)]
+    out: OutputArgs,
+
     #[command(flatten)]
     opts: CoreBuildArgs,
 }
 
 impl GenerateImmutableRouterArgs {
     pub fn run(&self) -> Result<()> {
-        if self.toml.is_some() {
-            self.run_toml()?;
-            return Ok(());
-        }
-
         // Merge all configs.
         let config = self.try_load_config_emit_warnings()?;
 
-        let project = config.create_project(true, true)?;
-
-        let sources = identify_sources(&project, self.module_names.clone())?;
-
-        let output = project.compile_files(sources)?;
-
-        if output.has_compiler_errors() {
-            println!("{output}");
-            eyre::bail!("Compilation failed");
-        }
-
-        let source = generate_router(
-            &project,
-            &output,
-            self.name.clone().unwrap(),
-            self.module_names.clone(),
+        let routers = load_routers(
+            self.toml.as_ref(),
+            self.name.as_ref(),
+            Router::new(self.module_names.clone(), self.unknown_selector.clone()),
         )?;
 
-        let router_file_path =
-            write_router(&project, &source, self.name.clone().unwrap().as_str())?;
-
-        println!(
-            "{} router file: {}",
-            Paint::green("Generated"),
-            router_file_path
-        );
-
-        Ok(())
-    }
-
-    fn run_toml(&self) -> Result<()> {
-        let config = self.try_load_config_emit_warnings()?;
-
-        let project = config.create_project(true, true)?;
-
-        let toml = TomlDefintion::from_path(self.toml.clone().unwrap().into())?;
-
-        let module_names: Vec<String> = toml
-            .routers
-            .iter()
-            .flat_map(|(_, router)| router.modules.clone())
+        let specs = routers
+            .into_iter()
+            .map(|(name, router)| RouterSpec::new(name, router, Flavor::Immutable))
             .collect();
 
-        let sources = identify_sources(&project, module_names.clone())?;
-
-        let output = project.compile_files(sources)?;
-
-        if output.has_compiler_errors() {
-            println!("{output}");
-            eyre::bail!("Compilation failed");
-        }
-
-        for (router_name, router) in toml.routers.iter() {
-            let source = generate_router(
-                &project,
-                &output,
-                router_name.clone(),
-                router.modules.clone(),
-            )?;
-
-            let router_file_path = write_router(&project, &source, router_name)?;
-
-            println!(
-                "{} router file: {}",
-                Paint::green("Generated"),
-                router_file_path
-            );
-        }
-
-        Ok(())
+        generate(config, &self.out, specs)
     }
 }
 
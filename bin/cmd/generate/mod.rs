@@ -1,12 +1,29 @@
-use std::{fs, path::Path};
+use std::{collections::HashSet, fs, path::Path};
 
+use alloy_primitives::hex::ToHexExt;
+use cannon_rs::generated::routers::{
+    pipeline::{configure, run_pipeline, GeneratedRouter, RouterSpec},
+    toml::{Router, TomlDefintion},
+};
 use clap::{Parser, Subcommand};
 use deterministic::GenerateRouterArgs;
 use eyre::Result;
+use facade::GenerateFacadeRouterArgs;
 use foundry_compilers::Project;
+use foundry_config::Config;
 use immutable::GenerateImmutableRouterArgs;
+use itertools::Itertools;
+use registry::GenerateRegistryRouterArgs;
+use serde::Serialize;
+use upgradeable::GenerateUpgradeableRouterArgs;
+use yansi::Paint;
+
+use crate::cmd::expand_module_patterns;
 mod deterministic;
+mod facade;
 mod immutable;
+mod registry;
+mod upgradeable;
 
 /// CLI arguments for `forge generate`.
 #[derive(Debug, Parser)]
@@ -20,20 +37,291 @@ pub enum GenerateSubcommands {
     /// Generate ERCXXX router.
     Router(Box<GenerateRouterArgs>),
     ImmutableRouter(Box<GenerateImmutableRouterArgs>),
+    /// Generate a UUPS router and its ERC-1967 proxy.
+    UpgradeableRouter(Box<GenerateUpgradeableRouterArgs>),
+    /// Generate a router resolving modules from an on-chain registry.
+    RegistryRouter(Box<GenerateRegistryRouterArgs>),
+    /// Generate a facade forwarding calls to independently deployed modules.
+    FacadeRouter(Box<GenerateFacadeRouterArgs>),
+}
+
+/// Where generated routers are sent.
+#[derive(Clone, Debug, Default, Serialize, Parser)]
+pub struct OutputArgs {
+    /// Output directory or file path relative to the project root, `{name}` is replaced by the
+    /// router name. Overrides the `output` of TOML routers.
+    #[clap(long, value_name = "PATH")]
+    pub out: Option<String>,
+
+    /// Print the generated routers to stdout instead of writing them.
+    #[clap(long, visible_alias = "dry-run", conflicts_with = "json")]
+    pub stdout: bool,
+
+    /// Print a JSON report of the generated routers.
+    #[clap(long)]
+    pub json: bool,
+
+    /// Storage layout snapshot of the modules relative to the project root, `{name}` is replaced
+    /// by the router name. Overrides the `storage_snapshot` of TOML routers.
+    #[clap(long, value_name = "PATH")]
+    pub storage_snapshot: Option<String>,
+
+    /// Accept storage layout changes breaking the snapshot and overwrite it.
+    #[clap(long)]
+    pub update_storage_snapshot: bool,
+
+    /// Also generate `<script>/Deploy<Router>.s.sol` for deterministic and immutable routers.
+    #[clap(long)]
+    pub deploy_script: bool,
+}
+
+impl OutputArgs {
+    /// Fail when `--out` would write several routers to the same file.
+    pub fn check_routers(&self, count: usize) -> Result<()> {
+        if let Some(out) = &self.out {
+            if count > 1 && !out.contains("{name}") && Path::new(out).extension().is_some() {
+                eyre::bail!(
+                    "`--out {out}` is a single file, use `{{name}}` to write {count} routers"
+                );
+            }
+        }
+        Ok(())
+    }
+
+    /// Write a generated file, or print it between delimiters in stdout mode. `kind` describes
+    /// the file in the report, e.g. `router` or `deployment script`.
+    ///
+    /// Unchanged files are left untouched, so that watchers do not see them modified. Returns
+    /// whether the file's contents changed.
+    pub fn emit(&self, path: &Path, contents: &str, kind: &str) -> Result<bool> {
+        if self.stdout {
+            let path = path.display();
+            println!("// ==== {path} ====");
+            println!("{}", contents.trim_end());
+            println!("// ==== end {path} ====");
+            return Ok(true);
+        }
+
+        if fs::read_to_string(path).is_ok_and(|existing| existing == contents) {
+            self.report(&format!(
+                "{} {kind} file: {}",
+                Paint::yellow("Unchanged"),
+                path.display()
+            ));
+            return Ok(false);
+        }
+
+        write_router_file(path, contents)?;
+        self.report(&format!(
+            "{} {kind} file: {}",
+            Paint::green("Generated"),
+            path.display()
+        ));
+
+        Ok(true)
+    }
+
+    /// Apply `--out`, `--storage-snapshot` and `--deploy-script` to the specs.
+    pub fn override_specs(&self, specs: Vec<RouterSpec>) -> Vec<RouterSpec> {
+        specs
+            .into_iter()
+            .map(|mut spec| {
+                spec.router.output = self.out.clone().or(spec.router.output);
+                spec.router.storage_snapshot = self
+                    .storage_snapshot
+                    .clone()
+                    .or(spec.router.storage_snapshot);
+                spec.router.deploy_script |= self.deploy_script && spec.flavor.has_deploy_script();
+                spec
+            })
+            .collect()
+    }
+
+    /// Whether stdout is reserved for generated routers or the JSON report.
+    pub fn stdout_reserved(&self) -> bool {
+        self.stdout || self.json
+    }
+
+    /// Print an informational line, kept off stdout when it is reserved.
+    pub fn report(&self, line: &str) {
+        if self.stdout_reserved() {
+            eprintln!("{line}");
+        } else {
+            println!("{line}");
+        }
+    }
+}
+
+pub fn write_router_file(path: &Path, contents: &str) -> Result<()> {
+    if let Some(output_dir) = path.parent() {
+        fs::create_dir_all(output_dir)?;
+    }
+    fs::write(path, contents)?;
+
+    Ok(())
+}
+
+/// The routers of a TOML file, or the router given on the command line.
+pub fn load_routers(
+    toml: Option<&String>,
+    name: Option<&String>,
+    router: Router,
+) -> Result<Vec<(String, Router)>> {
+    match toml {
+        Some(toml) => Ok(TomlDefintion::from_path(toml.into())?.sorted_routers()),
+        None => match name {
+            Some(name) => Ok(vec![(name.clone(), router)]),
+            None => eyre::bail!("Either `--toml` or `--name` is required"),
+        },
+    }
 }
 
-pub fn write_router(project: &Project, router: &str, router_name: &str) -> Result<String> {
-    let output_dir = project
-        .sources_path()
-        .as_path()
-        .to_path_buf()
-        .join("generated/routers");
+/// Create the project the specs are generated from, with the compiler outputs they need.
+pub fn create_project(
+    mut config: Config,
+    out: &OutputArgs,
+    specs: &[RouterSpec],
+) -> Result<Project> {
+    configure(&mut config, &out.override_specs(specs.to_vec()));
+    Ok(config.create_project(true, true)?)
+}
+
+/// Validate the specs, apply the output overrides and expand the module patterns of each spec.
+pub fn prepare_specs(
+    project: &Project,
+    out: &OutputArgs,
+    specs: Vec<RouterSpec>,
+) -> Result<Vec<RouterSpec>> {
+    out.check_routers(specs.iter().map(|s| &s.name).collect::<HashSet<_>>().len())?;
+    specs.iter().try_for_each(RouterSpec::validate)?;
+
+    out.override_specs(specs)
+        .into_iter()
+        .map(|mut spec| {
+            spec.router.modules =
+                expand_module_patterns(project, &spec.router.modules, out.stdout_reserved())?;
+            Ok(spec)
+        })
+        .collect()
+}
+
+/// Generate the specs and write or print them.
+///
+/// Returns the names of the routers whose files changed.
+pub fn emit_routers(
+    project: &Project,
+    out: &OutputArgs,
+    specs: &[RouterSpec],
+) -> Result<Vec<String>> {
+    let mut changed = vec![];
+
+    let routers = run_pipeline(project, specs)?;
+    check_storage_changes(out, &routers)?;
+
+    for router in &routers {
+        print_permissions(out, router);
+
+        for warning in &router.warnings {
+            out.report(&format!(
+                "{} {}: {warning}",
+                Paint::yellow("Warning"),
+                router.name
+            ));
+        }
+
+        if out.emit(&router.path, &router.source, "router")? && !changed.contains(&router.name) {
+            changed.push(router.name.clone());
+        }
+
+        if let Some((path, script)) = &router.deploy_script {
+            out.emit(path, script, "deployment script")?;
+        }
+
+        // Snapshots follow the routers, left untouched when nothing is written.
+        if let Some((path, snapshot)) = router.storage_snapshot.as_ref().filter(|_| !out.stdout) {
+            let snapshot = serde_json::to_string_pretty(snapshot)?;
+            if fs::read_to_string(path).map_or(true, |existing| existing != snapshot) {
+                write_router_file(path, &snapshot)?;
+                out.report(&format!(
+                    "{} storage snapshot: {}",
+                    Paint::green("Saved"),
+                    path.display()
+                ));
+            }
+        }
+    }
+
+    if out.json {
+        println!("{}", serde_json::to_string_pretty(&routers)?);
+    }
+
+    Ok(changed)
+}
+
+/// Fail before writing anything when module storage layouts broke their snapshots, unless
+/// `--update-storage-snapshot` accepts the changes.
+fn check_storage_changes(out: &OutputArgs, routers: &[GeneratedRouter]) -> Result<()> {
+    let changes = routers
+        .iter()
+        .flat_map(|r| r.storage_changes.iter().map(move |c| (&r.name, c)))
+        .map(|(name, change)| format!("{name}: {change}"))
+        .unique()
+        .collect::<Vec<String>>();
+
+    if changes.is_empty() {
+        return Ok(());
+    }
+
+    if out.update_storage_snapshot {
+        for change in &changes {
+            out.report(&format!(
+                "{} {change}",
+                Paint::yellow("Storage layout changed")
+            ));
+        }
+        return Ok(());
+    }
+
+    eyre::bail!(
+        "Storage layouts are incompatible with their snapshots:\n  {}\n\
+         Run with `--update-storage-snapshot` to accept the changes",
+        changes.join("\n  ")
+    )
+}
+
+/// Run a generate subcommand: create the project, then generate and emit every spec.
+pub fn generate(config: Config, out: &OutputArgs, specs: Vec<RouterSpec>) -> Result<()> {
+    let project = create_project(config, out, &specs)?;
+    let specs = prepare_specs(&project, out, specs)?;
+    emit_routers(&project, out, &specs)?;
+    Ok(())
+}
 
-    let output_dir = Path::new(&output_dir);
-    fs::create_dir_all(output_dir)?;
+/// Print which callers may reach each selector of a guarded router.
+pub fn print_permissions(out: &OutputArgs, router: &GeneratedRouter) {
+    if router.permissions.is_empty() {
+        return;
+    }
 
-    let router_file_path = output_dir.join(format!("{}.g.sol", router_name));
-    fs::write(&router_file_path, router)?;
+    out.report(&format!("{} permissions:", Paint::cyan(&router.name)));
+    for permission in &router.permissions {
+        let access = if permission.access.is_empty() {
+            "public".to_string()
+        } else {
+            permission
+                .access
+                .iter()
+                .map(|a| a.to_string())
+                .collect::<Vec<String>>()
+                .join(" + ")
+        };
 
-    Ok(router_file_path.as_path().to_str().unwrap().to_string())
+        out.report(&format!(
+            "  {} {}.{}() {}",
+            permission.selector.encode_hex_with_prefix(),
+            permission.contract_name,
+            permission.function_name,
+            access
+        ));
+    }
 }
@@ -0,0 +1,89 @@
+use cannon_rs::generated::routers::{
+    pipeline::{Flavor, RouterSpec},
+    toml::Router,
+    unknown::UnknownSelector,
+};
+use clap::Parser;
+use eyre::Result;
+use foundry_cli::{opts::CoreBuildArgs, utils::LoadConfig};
+use foundry_config::{
+    figment::{
+        value::{Dict, Map},
+        Metadata, Profile, Provider,
+    },
+    Config,
+};
+
+use crate::cmd::generate::{generate, load_routers, OutputArgs};
+
+// Loads project's figment and merges the build cli arguments into it
+foundry_config::merge_impl_figment_convert!(GenerateRegistryRouterArgs, opts);
+
+/// CLI arguments for `forge generate registry-router`.
+#[derive(Clone, Debug, Default, Parser)]
+pub struct GenerateRegistryRouterArgs {
+    /// Generate from TOML configuration file.
+    #[clap(long, value_name = "ROUTER_TOML")]
+    toml: Option<String>,
+
+    /// Router name for router generation.
+    #[clap(long, value_name = "ROUTER_NAME", conflicts_with = "toml")]
+    name: Option<String>,
+
+    /// Contract names for router generation.
+    #[clap(conflicts_with = "toml")]
+    module_names: Vec<String>,
+
+    /// Cache registry lookups in transient storage for the rest of the transaction.
+    #[clap(long, conflicts_with = "toml")]
+    cache: bool,
+
+    /// Behavior for unknown selectors: `revert`, `error:<SIGNATURE>`, `module:<MODULE>` or
+    /// `router:<ADDRESS>`.
+    #[clap(
+        long,
+        value_name = "BEHAVIOR",
+        default_value = "error:UnknownSelector(bytes4 sel)",
+        conflicts_with = "toml"
+    )]
+    unknown_selector: UnknownSelector,
+
+    #[command(flatten)]
+    out: OutputArgs,
+
+    #[command(flatten)]
+    opts: CoreBuildArgs,
+}
+
+impl GenerateRegistryRouterArgs {
+    pub fn run(&self) -> Result<()> {
+        // Merge all configs.
+        let config = self.try_load_config_emit_warnings()?;
+
+        let routers = load_routers(
+            self.toml.as_ref(),
+            self.name.as_ref(),
+            Router {
+                cache: self.cache,
+                ..Router::new(self.module_names.clone(), self.unknown_selector.clone())
+            },
+        )?;
+
+        let specs = routers
+            .into_iter()
+            .map(|(name, router)| RouterSpec::new(name, router, Flavor::Registry))
+            .collect();
+
+        generate(config, &self.out, specs)
+    }
+}
+
+impl Provider for GenerateRegistryRouterArgs {
+    fn metadata(&self) -> Metadata {
+        Metadata::named("Generator Args Provider")
+    }
+
+    fn data(&self) -> Result<Map<Profile, Dict>, foundry_config::figment::Error> {
+        Ok(Map::from([(Config::selected_profile(), Dict::default())]))
+    }
+}
@@ -0,0 +1,96 @@
+use alloy_primitives::{Address, B256};
+use cannon_rs::generated::routers::{
+    pipeline::{Flavor, RouterSpec},
+    toml::Router,
+    unknown::UnknownSelector,
+};
+use clap::Parser;
+use eyre::Result;
+use foundry_cli::{opts::CoreBuildArgs, utils::LoadConfig};
+use foundry_config::{
+    figment::{
+        value::{Dict, Map},
+        Metadata, Profile, Provider,
+    },
+    Config,
+};
+use serde::Serialize;
+
+use crate::cmd::generate::{generate, load_routers, OutputArgs};
+
+// Loads project's figment and merges the build cli arguments into it
+foundry_config::merge_impl_figment_convert!(GenerateUpgradeableRouterArgs, opts);
+
+/// CLI arguments for `forge generate upgradeable-router`.
+#[derive(Clone, Debug, Default, Serialize, Parser)]
+pub struct GenerateUpgradeableRouterArgs {
+    /// Generate from TOML configuration file.
+    #[clap(long, value_name = "ROUTER_TOML", conflicts_with_all = ["name", "module_names", "unknown_selector"])]
+    toml: Option<String>,
+
+    /// Router name for router generation.
+    #[clap(long, value_name = "ROUTER_NAME")]
+    name: Option<String>,
+
+    #[clap(long, default_value = "0x4e59b44847b379578588920ca78fbf26c0b4956c")]
+    deployer: Address,
+
+    #[clap(
+        long,
+        default_value = "0x0000000000000000000000000000000000000000000000000000000000000000"
+    )]
+    salt: B256,
+
+    /// Contract names for router generation.
+    module_names: Vec<String>,
+
+    /// Behavior for unknown selectors: `revert`, `error:<SIGNATURE>`, `module:<MODULE>` or
+    /// `router:<ADDRESS>`.
+    #[clap(
+        long,
+        value_name = "BEHAVIOR",
+        default_value = "error:UnknownSelector(bytes4 sel)"
+    )]
+    unknown_selector: UnknownSelector,
+
+    #[command(flatten)]
+    out: OutputArgs,
+
+    #[command(flatten)]
+    opts: CoreBuildArgs,
+}
+
+impl GenerateUpgradeableRouterArgs {
+    pub fn run(self) -> Result<()> {
+        // Merge all configs.
+        let config = self.try_load_config_emit_warnings()?;
+
+        let routers = load_routers(
+            self.toml.as_ref(),
+            self.name.as_ref(),
+            Router::new(self.module_names.clone(), self.unknown_selector.clone()),
+        )?;
+
+        let flavor = Flavor::Upgradeable {
+            deployer: self.deployer,
+            salt: self.salt,
+        };
+
+        let specs = routers
+            .into_iter()
+            .map(|(name, router)| RouterSpec::new(name, router, flavor))
+            .collect();
+
+        generate(config, &self.out, specs)
+    }
+}
+
+impl Provider for GenerateUpgradeableRouterArgs {
+    fn metadata(&self) -> Metadata {
+        Metadata::named("Generator Args Provider")
+    }
+
+    fn data(&self) -> Result<Map<Profile, Dict>, foundry_config::figment::Error> {
+        Ok(Map::from([(Config::selected_profile(), Dict::default())]))
+    }
+}
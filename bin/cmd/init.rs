@@ -0,0 +1,133 @@
+use std::fs;
+
+use alloy_primitives::hex::ToHexExt;
+use cannon_rs::generated::routers::scaffold::{
+    find_collisions, group_modules, render_toml, scan_modules, scan_sources,
+};
+use clap::Parser;
+use eyre::Result;
+use foundry_cli::{opts::CoreBuildArgs, utils::LoadConfig};
+use foundry_config::{
+    figment::{
+        value::{Dict, Map},
+        Metadata, Profile, Provider,
+    },
+    Config,
+};
+use itertools::Itertools;
+use serde::Serialize;
+use yansi::Paint;
+
+// Loads project's figment and merges the build cli arguments into it
+foundry_config::merge_impl_figment_convert!(InitArgs, opts);
+
+/// CLI arguments for `cannon-rs init`.
+#[derive(Clone, Debug, Default, Serialize, Parser)]
+pub struct InitArgs {
+    /// Files or directories to scan for modules, relative to the project root.
+    #[clap(default_value = "src/modules")]
+    paths: Vec<String>,
+
+    /// Name of the router, further routers are suffixed with a number.
+    #[clap(long, value_name = "ROUTER_NAME", default_value = "Router")]
+    name: String,
+
+    /// Path of the router TOML to write.
+    #[clap(long, value_name = "ROUTER_TOML", default_value = "router.toml")]
+    out: String,
+
+    /// Overwrite an existing router TOML.
+    #[clap(long)]
+    force: bool,
+
+    #[command(flatten)]
+    opts: CoreBuildArgs,
+}
+
+impl InitArgs {
+    pub fn run(self) -> Result<()> {
+        let mut config = self.try_load_config_emit_warnings()?;
+
+        // Interfaces, libraries and abstract contracts are told apart through the AST.
+        config.ast = true;
+
+        let project = config.create_project(true, true)?;
+
+        let toml_path = project.root().join(&self.out);
+        if toml_path.exists() && !self.force {
+            eyre::bail!(
+                "`{}` already exists, use `--force` to overwrite it",
+                toml_path.display()
+            );
+        }
+
+        let sources = scan_sources(&project, &self.paths)?;
+        if sources.is_empty() {
+            eyre::bail!("No Solidity files found in {}", self.paths.join(", "));
+        }
+
+        let output = project.compile_files(sources.clone())?;
+
+        if output.has_compiler_errors() {
+            println!("{output}");
+            eyre::bail!("Compilation failed");
+        }
+
+        let modules = scan_modules(&project, &output, &sources)?;
+        if modules.is_empty() {
+            eyre::bail!("No deployable contracts found in {}", self.paths.join(", "));
+        }
+
+        println!("{} modules:", Paint::cyan("Found"));
+        for module in &modules {
+            println!(
+                "  {} ({} selectors)",
+                module.identifier,
+                module.functions.len()
+            );
+            for (selector, signature) in &module.functions {
+                println!("    {} {signature}", selector.encode_hex_with_prefix());
+            }
+        }
+
+        let collisions = find_collisions(&modules);
+        if !collisions.is_empty() {
+            println!();
+            println!(
+                "{} selector collisions, colliding modules are split across routers:",
+                Paint::yellow("Found")
+            );
+            for collision in &collisions {
+                println!(
+                    "  {} {} in {}",
+                    collision.selector.encode_hex_with_prefix(),
+                    collision.signature,
+                    collision.modules.iter().join(", ")
+                );
+            }
+        }
+
+        let groups = group_modules(&modules);
+        fs::write(&toml_path, render_toml(&self.name, &groups))?;
+
+        println!();
+        println!(
+            "{} {} with {} router(s)",
+            Paint::green("Created"),
+            toml_path.display(),
+            groups.len()
+        );
+
+        Ok(())
+    }
+}
+
+impl Provider for InitArgs {
+    fn metadata(&self) -> Metadata {
+        Metadata::named("Init Args Provider")
+    }
+
+    fn data(&self) -> Result<Map<Profile, Dict>, foundry_config::figment::Error> {
+        Ok(Map::from([(Config::selected_profile(), Dict::default())]))
+    }
+}
@@ -0,0 +1,16 @@
+use clap::{Parser, Subcommand};
+use router::InspectRouterArgs;
+mod router;
+
+/// CLI arguments for `cannon-rs inspect`.
+#[derive(Debug, Parser)]
+pub struct InspectArgs {
+    #[command(subcommand)]
+    pub sub: InspectSubcommands,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum InspectSubcommands {
+    /// Print the selectors, modules and dispatch tree of a router.
+    Router(Box<InspectRouterArgs>),
+}
@@ -0,0 +1,226 @@
+use alloy_primitives::{hex::ToHexExt, Address, B256};
+use cannon_rs::generated::routers::{
+    inspect::{inspect_router, RouterInfo},
+    pipeline::{compile, Flavor, RouterSpec},
+    toml::Router,
+    unknown::UnknownSelector,
+};
+use clap::{Parser, ValueEnum};
+use eyre::Result;
+use foundry_cli::{opts::CoreBuildArgs, utils::LoadConfig};
+use foundry_config::{
+    figment::{
+        value::{Dict, Map},
+        Metadata, Profile, Provider,
+    },
+    Config,
+};
+use itertools::Itertools;
+use serde::Serialize;
+use yansi::Paint;
+
+use crate::cmd::generate::{create_project, load_routers, prepare_specs, OutputArgs};
+
+/// Router flavors, deciding whether module addresses are known before deployment.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, ValueEnum)]
+pub enum RouterKind {
+    /// Modules deployed with CREATE2 from `--deployer`.
+    #[default]
+    Deterministic,
+    /// UUPS router, modules deployed with CREATE2 from `--deployer`.
+    Upgradeable,
+    /// Module addresses set at construction.
+    Immutable,
+    /// Modules resolved from an on-chain registry.
+    Registry,
+    /// Calls forwarded to independently deployed modules.
+    Facade,
+}
+
+// Loads project's figment and merges the build cli arguments into it
+foundry_config::merge_impl_figment_convert!(InspectRouterArgs, opts);
+
+/// CLI arguments for `cannon-rs inspect router`.
+#[derive(Clone, Debug, Default, Serialize, Parser)]
+pub struct InspectRouterArgs {
+    /// Inspect the routers of a TOML configuration file.
+    #[clap(long, value_name = "ROUTER_TOML", conflicts_with_all = ["name", "module_names"])]
+    toml: Option<String>,
+
+    /// Router name.
+    #[clap(long, value_name = "ROUTER_NAME", default_value = "Router")]
+    name: String,
+
+    /// Flavor of the router, module addresses are only reported for CREATE2 flavors.
+    #[clap(long, value_enum, default_value_t = RouterKind::Deterministic)]
+    kind: RouterKind,
+
+    #[clap(long, default_value = "0x4e59b44847b379578588920ca78fbf26c0b4956c")]
+    deployer: Address,
+
+    #[clap(
+        long,
+        default_value = "0x0000000000000000000000000000000000000000000000000000000000000000"
+    )]
+    salt: B256,
+
+    /// Print the result as JSON.
+    #[clap(long)]
+    json: bool,
+
+    /// Contract names of the router's modules.
+    module_names: Vec<String>,
+
+    #[command(flatten)]
+    opts: CoreBuildArgs,
+}
+
+impl InspectRouterArgs {
+    pub fn run(self) -> Result<()> {
+        let config = self.try_load_config_emit_warnings()?;
+
+        let routers = load_routers(
+            self.toml.as_ref(),
+            Some(&self.name),
+            Router::new(self.module_names.clone(), UnknownSelector::default()),
+        )?;
+
+        let flavor = self.flavor();
+        let specs: Vec<RouterSpec> = routers
+            .into_iter()
+            .map(|(name, router)| RouterSpec::new(name, router, flavor))
+            .collect();
+
+        // Compile as the generate commands do, with the outputs hooks and patterns rely on.
+        let out = OutputArgs {
+            json: self.json,
+            ..Default::default()
+        };
+        let project = create_project(config, &out, &specs)?;
+        let specs = prepare_specs(&project, &out, specs)?;
+        let output = compile(&project, &specs)?;
+
+        let infos = specs
+            .iter()
+            .map(|spec| {
+                inspect_router(
+                    &project,
+                    &output,
+                    spec.name.clone(),
+                    spec.router.modules.clone(),
+                    spec.flavor,
+                )
+            })
+            .collect::<Result<Vec<RouterInfo>>>()?;
+
+        if self.json {
+            println!("{}", serde_json::to_string_pretty(&infos)?);
+        } else {
+            infos.iter().for_each(print_router);
+        }
+
+        Ok(())
+    }
+
+    fn flavor(&self) -> Flavor {
+        let (deployer, salt) = (self.deployer, self.salt);
+        match self.kind {
+            RouterKind::Deterministic => Flavor::Deterministic { deployer, salt },
+            RouterKind::Upgradeable => Flavor::Upgradeable { deployer, salt },
+            RouterKind::Immutable => Flavor::Immutable,
+            RouterKind::Registry => Flavor::Registry,
+            RouterKind::Facade => Flavor::Facade,
+        }
+    }
+}
+
+fn print_router(info: &RouterInfo) {
+    println!(
+        "{} ({} modules, {} selectors, max depth {})",
+        Paint::cyan(&info.name).bold(),
+        info.modules.len(),
+        info.selectors.len(),
+        info.max_depth
+    );
+
+    println!();
+    // Only CREATE2 flavors know module addresses ahead of deployment.
+    let addresses = info.modules.iter().any(|m| m.address.is_some());
+    let mut headers = vec!["Module", "Address", "Identifier", "Selectors"];
+    if !addresses {
+        headers.remove(1);
+    }
+    print_table(
+        &headers,
+        info.modules
+            .iter()
+            .map(|m| {
+                let mut row = vec![m.name.clone()];
+                if addresses {
+                    row.extend(m.address.map(|a| a.to_checksum(None)));
+                }
+                row.extend([m.identifier.clone(), m.selectors.to_string()]);
+                row
+            })
+            .collect(),
+    );
+
+    println!();
+    print_table(
+        &["Selector", "Signature", "Module", "Depth"],
+        info.selectors
+            .iter()
+            .map(|s| {
+                vec![
+                    s.selector.encode_hex_with_prefix(),
+                    s.signature.clone(),
+                    s.module.clone(),
+                    s.depth.to_string(),
+                ]
+            })
+            .collect(),
+    );
+    println!();
+}
+
+fn print_table(headers: &[&str], rows: Vec<Vec<String>>) {
+    let widths = headers
+        .iter()
+        .enumerate()
+        .map(|(i, header)| {
+            rows.iter()
+                .map(|row| row[i].len())
+                .chain(std::iter::once(header.len()))
+                .max()
+                .unwrap_or_default()
+        })
+        .collect::<Vec<usize>>();
+
+    let format_row = |cells: Vec<String>| {
+        cells
+            .iter()
+            .zip(&widths)
+            .map(|(cell, &width)| format!("{cell:<width$}"))
+            .join("  ")
+            .trim_end()
+            .to_string()
+    };
+
+    println!(
+        "{}",
+        Paint::bold(&format_row(headers.iter().map(|h| h.to_string()).collect()))
+    );
+    for row in rows {
+        println!("{}", format_row(row));
+    }
+}
+
+impl Provider for InspectRouterArgs {
+    fn metadata(&self) -> Metadata {
+        Metadata::named("Inspect Args Provider")
+    }
+
+    fn data(&self) -> Result<Map<Profile, Dict>, foundry_config::figment::Error> {
+        Ok(Map::from([(Config::selected_profile(), Dict::default())]))
+    }
+}
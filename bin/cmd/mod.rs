@@ -1 +1,41 @@
+use cannon_rs::generated::routers::{patterns::expand_modules, ModuleEntry};
+use eyre::Result;
+use foundry_compilers::Project;
+use yansi::Paint;
+
+pub mod diff;
 pub mod generate;
+pub mod init;
+pub mod inspect;
+pub mod verify;
+
+/// Expand the patterns of a module list, printing what each pattern matched.
+///
+/// The expansion goes to stderr when stdout is reserved for generated output.
+pub fn expand_module_patterns(
+    project: &Project,
+    modules: &[ModuleEntry],
+    stderr: bool,
+) -> Result<Vec<ModuleEntry>> {
+    let (modules, expansions) = expand_modules(project, modules)?;
+
+    let mut lines = vec![];
+    for expansion in expansions {
+        let action = match expansion.pattern.starts_with('!') {
+            true => Paint::yellow("Excluded"),
+            false => Paint::cyan("Expanded"),
+        };
+        lines.push(format!("{action} {}:", expansion.pattern));
+        lines.extend(expansion.modules.iter().map(|m| format!("  {m}")));
+    }
+
+    for line in lines {
+        if stderr {
+            eprintln!("{line}");
+        } else {
+            println!("{line}");
+        }
+    }
+
+    Ok(modules)
+}
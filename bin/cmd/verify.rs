@@ -0,0 +1,230 @@
+use alloy_primitives::{Address, B256};
+use alloy_provider::{Provider as _, ProviderBuilder};
+use cannon_rs::generated::routers::{
+    output::output_path,
+    pipeline::{compile, Flavor, RouterSpec},
+    toml::Router,
+    unknown::UnknownSelector,
+    verify::{compare, expected_modules, expected_router, Deployment, ExpectedCode, Status},
+};
+use clap::Parser;
+use eyre::Result;
+use foundry_cli::{opts::CoreBuildArgs, utils::LoadConfig};
+use foundry_config::{
+    figment::{
+        value::{Dict, Map},
+        Metadata, Profile, Provider,
+    },
+    Config,
+};
+use serde::Serialize;
+use yansi::Paint;
+
+use crate::cmd::generate::{create_project, load_routers, prepare_specs, OutputArgs};
+
+// Loads project's figment and merges the build cli arguments into it
+foundry_config::merge_impl_figment_convert!(VerifyArgs, opts);
+
+/// CLI arguments for `cannon-rs verify`.
+#[derive(Clone, Debug, Default, Serialize, Parser)]
+pub struct VerifyArgs {
+    /// Verify the routers of a TOML configuration file.
+    #[clap(long, value_name = "ROUTER_TOML", conflicts_with_all = ["name", "module_names", "router_address"])]
+    toml: Option<String>,
+
+    /// Router name.
+    #[clap(long, value_name = "ROUTER_NAME", default_value = "Router")]
+    name: String,
+
+    /// Contract names of the router's modules.
+    module_names: Vec<String>,
+
+    #[clap(long, default_value = "0x4e59b44847b379578588920ca78fbf26c0b4956c")]
+    deployer: Address,
+
+    #[clap(
+        long,
+        default_value = "0x0000000000000000000000000000000000000000000000000000000000000000"
+    )]
+    salt: B256,
+
+    /// Address of the deployed router, defaults to its CREATE2 address from `--deployer`.
+    #[clap(long, value_name = "ADDRESS")]
+    router_address: Option<Address>,
+
+    /// The RPC endpoint to fetch deployed code from.
+    #[clap(
+        long,
+        value_name = "URL",
+        env = "ETH_RPC_URL",
+        default_value = "http://localhost:8545"
+    )]
+    rpc_url: String,
+
+    /// Ignore the CBOR metadata appended by the compiler.
+    #[clap(long)]
+    ignore_metadata: bool,
+
+    /// Print the result as JSON.
+    #[clap(long)]
+    json: bool,
+
+    #[command(flatten)]
+    opts: CoreBuildArgs,
+}
+
+/// Verification results of a router and its modules.
+#[derive(Debug, Serialize)]
+struct RouterVerification {
+    name: String,
+    /// `None` when the router has not been generated in this project.
+    router: Option<Deployment>,
+    modules: Vec<Deployment>,
+}
+
+impl VerifyArgs {
+    pub fn run(self) -> Result<()> {
+        let config = self.try_load_config_emit_warnings()?;
+
+        let routers = load_routers(
+            self.toml.as_ref(),
+            Some(&self.name),
+            Router::new(self.module_names.clone(), UnknownSelector::default()),
+        )?;
+
+        let flavor = Flavor::Deterministic {
+            deployer: self.deployer,
+            salt: self.salt,
+        };
+        let specs: Vec<RouterSpec> = routers
+            .into_iter()
+            .map(|(name, router)| RouterSpec::new(name, router, flavor))
+            .collect();
+
+        let out = OutputArgs {
+            json: self.json,
+            ..Default::default()
+        };
+        let project = create_project(config, &out, &specs)?;
+        let specs = prepare_specs(&project, &out, specs)?;
+
+        // Generated routers are verified alongside their modules when present.
+        let mut compiled = specs.clone();
+        let mut router_paths = vec![];
+        for spec in &specs {
+            let path = output_path(&project, spec.router.output.as_deref(), &spec.name, "sol");
+            if path.is_file() {
+                let relative = path.strip_prefix(project.root()).unwrap_or(&path);
+                let identifier = format!("{}:{}", relative.display(), spec.name);
+                let router = Router::new(vec![identifier], UnknownSelector::default());
+                compiled.push(RouterSpec::new(spec.name.clone(), router, flavor));
+            }
+            router_paths.push(path);
+        }
+
+        let output = compile(&project, &compiled)?;
+
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()?;
+        let provider = ProviderBuilder::new().on_http(self.rpc_url.parse()?);
+
+        let mut results = vec![];
+        for (spec, path) in specs.iter().zip(router_paths) {
+            let router = match path.is_file() {
+                true => Some(expected_router(
+                    &project,
+                    &output,
+                    &spec.name,
+                    &path,
+                    self.router_address,
+                    self.deployer,
+                    self.salt,
+                )?),
+                false => None,
+            };
+            let modules = expected_modules(
+                &project,
+                &output,
+                spec.router.modules.clone(),
+                self.deployer,
+                self.salt,
+            )?;
+
+            let verify = |expected: &ExpectedCode| -> Result<Deployment> {
+                let code =
+                    runtime.block_on(async { provider.get_code_at(expected.address).await })?;
+                Ok(Deployment {
+                    name: expected.name.clone(),
+                    address: expected.address,
+                    status: compare(expected, &code, self.ignore_metadata),
+                })
+            };
+
+            results.push(RouterVerification {
+                name: spec.name.clone(),
+                router: router.as_ref().map(&verify).transpose()?,
+                modules: modules
+                    .iter()
+                    .map(&verify)
+                    .collect::<Result<Vec<Deployment>>>()?,
+            });
+        }
+
+        let failed = results.iter().any(|r| {
+            r.router
+                .iter()
+                .chain(&r.modules)
+                .any(|d| d.status != Status::Verified)
+        });
+
+        if self.json {
+            println!("{}", serde_json::to_string_pretty(&results)?);
+        } else {
+            results.iter().for_each(print_verification);
+        }
+
+        if failed {
+            eyre::bail!("Some deployments are missing or do not match their artifacts");
+        }
+
+        Ok(())
+    }
+}
+
+fn print_verification(verification: &RouterVerification) {
+    println!("{}", Paint::cyan(&verification.name).bold());
+
+    match &verification.router {
+        Some(router) => print_deployment(router),
+        None => println!(
+            "  {} router not generated, only modules are verified",
+            Paint::yellow("skipped")
+        ),
+    }
+    verification.modules.iter().for_each(print_deployment);
+    println!();
+}
+
+fn print_deployment(deployment: &Deployment) {
+    let status = match deployment.status {
+        Status::Verified => Paint::green("verified "),
+        Status::Missing => Paint::red("missing  "),
+        Status::Mismatch => Paint::red("mismatch "),
+    };
+    println!(
+        "  {status}{} {}",
+        deployment.address.to_checksum(None),
+        deployment.name
+    );
+}
+
+impl Provider for VerifyArgs {
+    fn metadata(&self) -> Metadata {
+        Metadata::named("Verify Args Provider")
+    }
+
+    fn data(&self) -> Result<Map<Profile, Dict>, foundry_config::figment::Error> {
+        Ok(Map::from([(Config::selected_profile(), Dict::default())]))
+    }
+}
@@ -3,7 +3,7 @@ use eyre::Result;
 use foundry_cli::{handler, utils};
 
 mod cmd;
-use cmd::generate::GenerateSubcommands;
+use cmd::{generate::GenerateSubcommands, inspect::InspectSubcommands};
 
 mod opts;
 use opts::{Cannon, CannonSubCommand};
@@ -20,6 +20,15 @@ fn main() -> Result<()> {
         CannonSubCommand::Generate(cmd) => match cmd.sub {
             GenerateSubcommands::Router(cmd) => cmd.run(),
             GenerateSubcommands::ImmutableRouter(cmd) => cmd.run(),
+            GenerateSubcommands::UpgradeableRouter(cmd) => cmd.run(),
+            GenerateSubcommands::RegistryRouter(cmd) => cmd.run(),
+            GenerateSubcommands::FacadeRouter(cmd) => cmd.run(),
         },
+        CannonSubCommand::Inspect(cmd) => match cmd.sub {
+            InspectSubcommands::Router(cmd) => cmd.run(),
+        },
+        CannonSubCommand::Init(cmd) => cmd.run(),
+        CannonSubCommand::Verify(cmd) => cmd.run(),
+        CannonSubCommand::Diff(cmd) => cmd.run(),
     }
 }
@@ -1,4 +1,4 @@
-use crate::cmd::generate;
+use crate::cmd::{diff, generate, init, inspect, verify};
 use clap::{Parser, Subcommand};
 
 const VERSION_MESSAGE: &str = concat!(
@@ -27,6 +27,14 @@ pub struct Cannon {
 pub enum CannonSubCommand {
     /// Generate scaffold files.
     Generate(generate::GenerateArgs),
+    /// Inspect router definitions without generating files.
+    Inspect(inspect::InspectArgs),
+    /// Scaffold a router TOML from the project's contracts.
+    Init(init::InitArgs),
+    /// Check deployed routers and modules against the compiled artifacts.
+    Verify(verify::VerifyArgs),
+    /// Compare the routed functions of two router versions.
+    Diff(diff::DiffArgs),
 }
 
 #[cfg(test)]
@@ -1,19 +1,31 @@
+use std::collections::HashMap;
+
 use alloy_json_abi::JsonAbi;
-use alloy_primitives::{hex::ToHexExt, Address, B256};
-use eyre::Result;
+use alloy_primitives::{hex::ToHexExt, Address, Selector, B256};
+use eyre::{eyre, Result};
 use foundry_compilers::{Project, ProjectCompileOutput};
+use itertools::Itertools;
+
+use super::{
+    build_binary_tree, collect_sources, render_modules_with_template, render_tree,
+    unknown::UnknownSelector, DispatchOptions, Module, ModuleEntry,
+};
 
-use crate::generated::routers::utils::to_constant_case;
+/// Identifiers the Solidity router declares next to the module constants.
+pub const ROUTER_IDENTIFIERS: &[&str] = &["FEATURES_SLOT"];
 
-use super::{render_modules_with_template, Module};
+/// The Yul router declares no identifiers of its own, module addresses are inlined.
+pub const YUL_ROUTER_IDENTIFIERS: &[&str] = &[];
 
 pub fn generate_router(
     project: &Project,
     output: &ProjectCompileOutput,
     router_name: String,
-    module_names: Vec<String>,
+    module_names: Vec<ModuleEntry>,
     deployer: Address,
     salt: B256,
+    unknown_selector: &UnknownSelector,
+    dispatch: &DispatchOptions,
 ) -> Result<String> {
     super::generate_router(
         project,
@@ -22,36 +34,168 @@ pub fn generate_router(
         module_names,
         Some(deployer),
         Some(salt),
-        &|m: &Module| {
-            format!(
-                "case {} {{ result := {} }} // {}.{}()",
-                m.selector.encode_hex_with_prefix(),
-                to_constant_case(&m.contract_name),
-                m.contract_name,
-                m.function_name
-            )
+        ROUTER_IDENTIFIERS,
+        dispatch,
+        &|m: &Module| format!("result := {}", m.constant_name),
+        &|router_name: &String, modules: &Vec<Module>, abi: &JsonAbi| {
+            template(router_name, modules, abi, unknown_selector)
         },
-        &template,
     )
 }
 
-fn template(router_name: &String, modules: &Vec<Module>, abi: &JsonAbi) -> Result<String> {
+fn template(
+    router_name: &String,
+    modules: &Vec<Module>,
+    abi: &JsonAbi,
+    unknown_selector: &UnknownSelector,
+) -> Result<String> {
+    let abi = unknown_selector.extend_abi(abi)?;
     let interface = abi.to_sol(format!("I{router_name}").as_str(), None);
 
     let module_lookup = render_modules_with_template(modules, &|m| {
         format!(
             "    address constant {} = {};",
-            to_constant_case(&m.contract_name),
+            m.constant_name,
             &m.address.unwrap().to_checksum(None)
         )
     });
 
+    let unknown_selector_error = unknown_selector.render_declaration()?;
+    let unknown_selector_fallback = unknown_selector.render_fallback(
+        modules,
+        |m| m.constant_name.clone(),
+        |target| format!("implementation = {target};"),
+    )?;
+
     // Create the router file content.
     let router_content = include_str!("../../../assets/templates/RouterTemplate.sol");
     let router_content = router_content
         .replace("{interface}", &interface)
         .replace("{router_name}", &router_name)
-        .replace("{modules}", &module_lookup);
+        .replace("{modules}", &module_lookup)
+        .replace("{unknown_selector_error}\n", &unknown_selector_error)
+        .replace("{unknown_selector}", &unknown_selector_fallback);
+
+    Ok(router_content)
+}
+
+/// Generate the router as a standalone Yul object, compilable with `solc --strict-assembly`.
+///
+/// Hooks, guards and feature flags need the Solidity wrapper and are not supported here.
+pub fn generate_yul_router(
+    project: &Project,
+    output: &ProjectCompileOutput,
+    router_name: String,
+    module_names: Vec<ModuleEntry>,
+    deployer: Address,
+    salt: B256,
+    unknown_selector: &UnknownSelector,
+    dispatch: &DispatchOptions,
+) -> Result<String> {
+    if !dispatch.is_empty() {
+        return Err(eyre!(
+            "Hooks, guards and features are not supported in Yul output for `{router_name}`"
+        ));
+    }
+
+    let (selectors, _) = collect_sources(
+        project,
+        output,
+        module_names,
+        Some(deployer),
+        Some(salt),
+        YUL_ROUTER_IDENTIFIERS,
+    )?;
+
+    yul_template(&router_name, &selectors, unknown_selector)
+}
+
+/// Render the Yul router dispatching the given selectors.
+fn yul_template(
+    router_name: &str,
+    selectors: &HashMap<Selector, Module>,
+    unknown_selector: &UnknownSelector,
+) -> Result<String> {
+    let tree = build_binary_tree(selectors.keys().cloned().collect::<Vec<Selector>>());
+    let tree = render_tree(&tree, selectors, &Default::default(), |m: &Module| {
+        format!("result := {}", m.address.unwrap().encode_hex_with_prefix())
+    });
+
+    let modules = selectors
+        .values()
+        .sorted_by(|a, b| a.contract_name.cmp(&b.contract_name))
+        .cloned()
+        .collect::<Vec<Module>>();
+    let module_list = render_modules_with_template(&modules, &|m| {
+        format!(
+            "// {} = {}",
+            m.contract_name,
+            m.address.unwrap().to_checksum(None)
+        )
+    });
+
+    let router_content = include_str!("../../../assets/templates/RouterTemplate.yul");
+    let router_content = router_content
+        .replace("{router_name}", router_name)
+        .replace("{modules}", &module_list)
+        .replace(
+            "{unknown_selector}",
+            &unknown_selector.render_yul_fallback(&modules)?,
+        )
+        .replace("{selectors}", &tree);
 
     Ok(router_content)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::super::tests::{module, routed};
+    use super::*;
+
+    #[test]
+    fn renders_a_standalone_yul_router() {
+        let vault = module("src/Vault.sol:Vault", "deposit(uint256)");
+        let token = module("src/Token.sol:Token", "transfer(address,uint256)");
+        let selectors = routed([
+            vault.clone(),
+            module("src/Vault.sol:Vault", "withdraw(uint256)"),
+            token.clone(),
+        ]);
+
+        let rendered = yul_template("Router", &selectors, &UnknownSelector::Revert).unwrap();
+
+        assert!(rendered.contains("object \"Router\" {"));
+        for m in [&vault, &token] {
+            let address = m.address.unwrap();
+            assert_eq!(
+                rendered
+                    .matches(&format!(
+                        "// {} = {}",
+                        m.contract_name,
+                        address.to_checksum(None)
+                    ))
+                    .count(),
+                1
+            );
+            assert!(rendered.contains(&format!("result := {}", address.encode_hex_with_prefix())));
+        }
+        assert!(rendered.contains("                revert(0, 0)"));
+        assert!(!rendered.contains("{selectors}") && !rendered.contains("{unknown_selector}"));
+    }
+
+    #[test]
+    fn forwards_unknown_selectors_to_a_module() {
+        let fallback = module("src/Fallback.sol:Fallback", "fallbackCall()");
+        let selectors = routed([fallback.clone()]);
+        let unknown_selector = UnknownSelector::Module("Fallback".to_string());
+
+        let rendered = yul_template("Router", &selectors, &unknown_selector).unwrap();
+        assert!(rendered.contains(&format!(
+            "implementation := {}",
+            fallback.address.unwrap().encode_hex_with_prefix()
+        )));
+
+        let missing = UnknownSelector::Module("Missing".to_string());
+        assert!(yul_template("Router", &selectors, &missing).is_err());
+    }
+}
@@ -0,0 +1,349 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use alloy_json_abi::JsonAbi;
+use alloy_primitives::Selector;
+use eyre::{eyre, Result};
+use serde::{Deserialize, Serialize};
+
+use super::inspect::RouterInfo;
+
+/// A function reachable through a router.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct RoutedFunction {
+    pub selector: Selector,
+    pub signature: String,
+    /// The module implementing the function, unknown for plain ABIs.
+    pub module: Option<String>,
+}
+
+impl RoutedFunction {
+    /// The function name, the signature up to its parameters.
+    pub fn name(&self) -> &str {
+        self.signature
+            .split_once('(')
+            .map_or(self.signature.as_str(), |(name, _)| name)
+    }
+}
+
+/// The routed functions of a router by selector.
+pub type Surface = BTreeMap<Selector, RoutedFunction>;
+
+/// A difference between two versions of a router's surface.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "change", rename_all = "snake_case")]
+pub enum Change {
+    Added {
+        function: RoutedFunction,
+    },
+    Removed {
+        function: RoutedFunction,
+    },
+    /// The selector is now implemented by another module.
+    Moved {
+        function: RoutedFunction,
+        from: String,
+    },
+    /// A function kept its name but its overloads' signatures changed.
+    SignatureChanged {
+        name: String,
+        removed: Vec<RoutedFunction>,
+        added: Vec<RoutedFunction>,
+    },
+}
+
+impl Change {
+    /// Whether callers of the previous version may break, i.e. a selector went away.
+    pub fn is_breaking(&self) -> bool {
+        matches!(self, Self::Removed { .. } | Self::SignatureChanged { .. })
+    }
+}
+
+/// The surface of a router as reported by `inspect_router`.
+pub fn surface_from_info(info: &RouterInfo) -> Surface {
+    info.selectors
+        .iter()
+        .map(|s| {
+            let function = RoutedFunction {
+                selector: s.selector,
+                signature: s.signature.clone(),
+                module: Some(s.module.clone()),
+            };
+            (s.selector, function)
+        })
+        .collect()
+}
+
+/// The surface of a plain ABI, modules being unknown.
+pub fn surface_from_abi(abi: &JsonAbi) -> Surface {
+    abi.functions()
+        .map(|f| {
+            let function = RoutedFunction {
+                selector: f.selector(),
+                signature: f.signature(),
+                module: None,
+            };
+            (f.selector(), function)
+        })
+        .collect()
+}
+
+/// A contract artifact as written by the compiler, only its ABI is read.
+#[derive(Deserialize)]
+struct Artifact {
+    abi: JsonAbi,
+}
+
+/// Read router surfaces from an exported JSON file.
+///
+/// Accepts the `--json` output of `inspect router` and of the generate commands, keyed by router
+/// name, or a contract artifact or plain ABI, keyed by the empty string. An empty list is read as
+/// an ABI without functions.
+pub fn surfaces_from_json(content: &str) -> Result<BTreeMap<String, Surface>> {
+    let infos = serde_json::from_str::<Vec<RouterInfo>>(content);
+    if let Some(infos) = infos.ok().filter(|infos| !infos.is_empty()) {
+        return Ok(infos
+            .iter()
+            .map(|info| (info.name.clone(), surface_from_info(info)))
+            .collect());
+    }
+
+    let abi = match serde_json::from_str::<Artifact>(content) {
+        Ok(artifact) => artifact.abi,
+        Err(_) => serde_json::from_str::<JsonAbi>(content)
+            .map_err(|e| eyre!("Expected a router report, a contract artifact or an ABI: {e}"))?,
+    };
+    Ok(BTreeMap::from([(String::new(), surface_from_abi(&abi))]))
+}
+
+/// Compare two versions of a router's surface.
+pub fn diff_surfaces(old: &Surface, new: &Surface) -> Vec<Change> {
+    let mut removed = old
+        .values()
+        .filter(|f| !new.contains_key(&f.selector))
+        .cloned()
+        .collect::<Vec<RoutedFunction>>();
+    let mut added = new
+        .values()
+        .filter(|f| !old.contains_key(&f.selector))
+        .cloned()
+        .collect::<Vec<RoutedFunction>>();
+
+    let mut changes = vec![];
+
+    // A name losing and gaining selectors had its signature changed.
+    let removed_names = removed
+        .iter()
+        .map(|f| f.name().to_string())
+        .collect::<BTreeSet<_>>();
+    let added_names = added
+        .iter()
+        .map(|f| f.name().to_string())
+        .collect::<BTreeSet<_>>();
+    for name in removed_names.intersection(&added_names) {
+        let (name_removed, rest): (Vec<_>, Vec<_>) =
+            removed.into_iter().partition(|f| f.name() == name);
+        removed = rest;
+        let (name_added, rest): (Vec<_>, Vec<_>) =
+            added.into_iter().partition(|f| f.name() == name);
+        added = rest;
+
+        changes.push(Change::SignatureChanged {
+            name: name.clone(),
+            removed: name_removed,
+            added: name_added,
+        });
+    }
+
+    changes.extend(
+        removed
+            .into_iter()
+            .map(|function| Change::Removed { function }),
+    );
+    changes.extend(added.into_iter().map(|function| Change::Added { function }));
+
+    for (selector, function) in new {
+        let Some(previous) = old.get(selector) else {
+            continue;
+        };
+        if let (Some(from), Some(to)) = (&previous.module, &function.module) {
+            if from != to {
+                changes.push(Change::Moved {
+                    function: function.clone(),
+                    from: from.clone(),
+                });
+            }
+        }
+    }
+
+    changes
+}
+
+/// Pair the routers of two versions and diff each pair.
+///
+/// Routers are matched by name, except when both sides hold a single router, which are compared
+/// whatever their names. Routers missing from one side have all their functions added or removed.
+pub fn diff_routers(
+    old: &BTreeMap<String, Surface>,
+    new: &BTreeMap<String, Surface>,
+) -> BTreeMap<String, Vec<Change>> {
+    if old.len() == 1 && new.len() == 1 {
+        let (old_name, old_surface) = old.iter().next().unwrap();
+        let (new_name, new_surface) = new.iter().next().unwrap();
+        let name = match new_name.is_empty() {
+            true => old_name.clone(),
+            false => new_name.clone(),
+        };
+        return BTreeMap::from([(name, diff_surfaces(old_surface, new_surface))]);
+    }
+
+    let empty = Surface::new();
+    old.keys()
+        .chain(new.keys())
+        .collect::<BTreeSet<&String>>()
+        .into_iter()
+        .map(|name| {
+            let changes = diff_surfaces(
+                old.get(name).unwrap_or(&empty),
+                new.get(name).unwrap_or(&empty),
+            );
+            (name.clone(), changes)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy_json_abi::Function;
+
+    use super::*;
+
+    fn surface(functions: &[(&str, Option<&str>)]) -> Surface {
+        functions
+            .iter()
+            .map(|(signature, module)| {
+                let function = Function::parse(signature).unwrap();
+                let function = RoutedFunction {
+                    selector: function.selector(),
+                    signature: function.signature(),
+                    module: module.map(str::to_string),
+                };
+                (function.selector, function)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn unchanged_surface_has_no_changes() {
+        let old = surface(&[("balanceOf(address)", Some("TokenModule"))]);
+        assert!(diff_surfaces(&old, &old).is_empty());
+    }
+
+    #[test]
+    fn detects_added_removed_and_moved_functions() {
+        let old = surface(&[
+            ("balanceOf(address)", Some("TokenModule")),
+            ("owner()", Some("OwnerModule")),
+            ("pause()", Some("OwnerModule")),
+        ]);
+        let new = surface(&[
+            ("balanceOf(address)", Some("BalanceModule")),
+            ("owner()", Some("OwnerModule")),
+            ("unpause()", Some("OwnerModule")),
+        ]);
+
+        let changes = diff_surfaces(&old, &new);
+        assert_eq!(changes.len(), 3);
+        assert!(matches!(
+            &changes[0],
+            Change::Removed { function } if function.signature == "pause()"
+        ));
+        assert!(matches!(
+            &changes[1],
+            Change::Added { function } if function.signature == "unpause()"
+        ));
+        assert!(matches!(
+            &changes[2],
+            Change::Moved { function, from }
+                if function.signature == "balanceOf(address)" && from == "TokenModule"
+        ));
+        assert!(changes[0].is_breaking());
+        assert!(!changes[1].is_breaking());
+        assert!(!changes[2].is_breaking());
+    }
+
+    #[test]
+    fn groups_signature_changes_by_name() {
+        let old = surface(&[("transfer(address,uint256)", None)]);
+        let new = surface(&[
+            ("transfer(address,uint128)", None),
+            ("transfer(address,uint128,bytes)", None),
+        ]);
+
+        let changes = diff_surfaces(&old, &new);
+        assert_eq!(changes.len(), 1);
+        let Change::SignatureChanged {
+            name,
+            removed,
+            added,
+        } = &changes[0]
+        else {
+            panic!("Expected a signature change, got {:?}", changes[0]);
+        };
+        assert_eq!(name, "transfer");
+        assert_eq!(removed.len(), 1);
+        assert_eq!(added.len(), 2);
+        assert!(changes[0].is_breaking());
+    }
+
+    #[test]
+    fn reads_artifacts_and_plain_abis() {
+        let abi = r#"[{
+            "type": "function",
+            "name": "owner",
+            "inputs": [],
+            "outputs": [{ "name": "", "type": "address", "internalType": "address" }],
+            "stateMutability": "view"
+        }]"#;
+        let artifact = format!(r#"{{ "abi": {abi}, "bytecode": {{ "object": "0x" }} }}"#);
+
+        for content in [abi, artifact.as_str()] {
+            let surfaces = surfaces_from_json(content).unwrap();
+            assert_eq!(surfaces.len(), 1);
+            let functions = surfaces[""].values().collect::<Vec<_>>();
+            assert_eq!(functions.len(), 1);
+            assert_eq!(functions[0].signature, "owner()");
+            assert_eq!(functions[0].module, None);
+        }
+
+        assert!(surfaces_from_json(r#"{ "bytecode": "0x" }"#).is_err());
+    }
+
+    #[test]
+    fn reads_empty_lists_as_routers_without_functions() {
+        let surfaces = surfaces_from_json("[]").unwrap();
+        assert_eq!(surfaces.len(), 1);
+        assert!(surfaces[""].is_empty());
+
+        let old = BTreeMap::from([("Router".to_string(), surface(&[("owner()", None)]))]);
+        let changes = diff_routers(&old, &surfaces);
+        assert!(matches!(&changes["Router"][..], [Change::Removed { .. }]));
+    }
+
+    #[test]
+    fn pairs_single_routers_whatever_their_names() {
+        let old = BTreeMap::from([("Router".to_string(), surface(&[("owner()", None)]))]);
+        let new = BTreeMap::from([(String::new(), surface(&[]))]);
+
+        let changes = diff_routers(&old, &new);
+        assert_eq!(changes.len(), 1);
+        assert!(matches!(&changes["Router"][..], [Change::Removed { .. }]));
+
+        let new = BTreeMap::from([
+            ("Router".to_string(), surface(&[("owner()", None)])),
+            ("Other".to_string(), surface(&[("owner()", None)])),
+        ]);
+        let changes = diff_routers(&old, &new);
+        assert!(changes["Router"].is_empty());
+        assert!(matches!(&changes["Other"][..], [Change::Added { .. }]));
+    }
+}
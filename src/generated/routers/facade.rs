@@ -0,0 +1,95 @@
+use alloy_json_abi::{JsonAbi, StateMutability};
+use eyre::Result;
+use foundry_compilers::{Project, ProjectCompileOutput};
+
+use super::{
+    render_modules_with_template, unknown::UnknownSelector, DispatchOptions, Module, ModuleEntry,
+};
+
+/// Identifiers the facade declares next to the module immutables.
+pub const ROUTER_IDENTIFIERS: &[&str] = &["FEATURES_SLOT", "Modules"];
+
+/// Generate a facade that forwards calls to independently deployed modules.
+///
+/// Unlike the other flavors, modules are reached with `call` (or `staticcall` for `view` and
+/// `pure` functions), so they execute against their own storage and see the facade as
+/// `msg.sender`.
+pub fn generate_router(
+    project: &Project,
+    output: &ProjectCompileOutput,
+    router_name: String,
+    module_names: Vec<ModuleEntry>,
+    unknown_selector: &UnknownSelector,
+    dispatch: &DispatchOptions,
+) -> Result<String> {
+    super::generate_router(
+        project,
+        output,
+        router_name,
+        module_names,
+        None,
+        None,
+        ROUTER_IDENTIFIERS,
+        dispatch,
+        &|m: &Module| {
+            let read_only = matches!(
+                m.state_mutability,
+                StateMutability::View | StateMutability::Pure
+            );
+            format!(
+                "result := {} readOnly := {}",
+                m.contract_identifier, read_only as u8
+            )
+        },
+        &|router_name: &String, modules: &Vec<Module>, abi: &JsonAbi| {
+            template(router_name, modules, abi, unknown_selector)
+        },
+    )
+}
+
+fn template(
+    router_name: &String,
+    modules: &Vec<Module>,
+    abi: &JsonAbi,
+    unknown_selector: &UnknownSelector,
+) -> Result<String> {
+    let abi = unknown_selector.extend_abi(abi)?;
+    let interface = abi.to_sol(format!("I{router_name}").as_str(), None);
+
+    let module_lookup = render_modules_with_template(modules, &|m| {
+        format!("    address immutable internal {};", m.constant_name)
+    });
+    let resolver = render_modules_with_template(modules, &|m| {
+        format!(
+            "        if (implementation == {}) return {};",
+            m.contract_identifier, m.constant_name
+        )
+    });
+    let immutables = render_modules_with_template(modules, &|m| {
+        format!("        {} = $.{};", m.constant_name, m.variable_name)
+    });
+    let struct_str = render_modules_with_template(modules, &|m| {
+        format!("        address {};", m.variable_name)
+    });
+
+    let unknown_selector_error = unknown_selector.render_declaration()?;
+    let unknown_selector_fallback = unknown_selector.render_fallback(
+        modules,
+        |m| m.constant_name.clone(),
+        |target| format!("implementation_address = {target};"),
+    )?;
+
+    // Create the router file content.
+    let router_content = include_str!("../../../assets/templates/FacadeRouterTemplate.sol");
+    let router_content = router_content
+        .replace("{resolver}", &resolver)
+        .replace("{interface}", &interface)
+        .replace("{router_name}", &router_name)
+        .replace("{immutables}", &immutables)
+        .replace("{struct}", &struct_str)
+        .replace("{modules}", &module_lookup)
+        .replace("{unknown_selector_error}\n", &unknown_selector_error)
+        .replace("{unknown_selector}", &unknown_selector_fallback);
+
+    Ok(router_content)
+}
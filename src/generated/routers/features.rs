@@ -0,0 +1,322 @@
+use std::collections::{BTreeMap, HashMap};
+
+use alloy_json_abi::{Error, Event, Function, JsonAbi};
+use alloy_primitives::{hex::ToHexExt, keccak256, Selector, B256};
+use eyre::{eyre, Result};
+use serde::Deserialize;
+
+use super::{
+    guards::{render_access_check, render_unauthorized, Access, UNAUTHORIZED_ERROR},
+    scope::Scope,
+    storage::erc7201_slot,
+    Module,
+};
+
+/// ERC-7201 namespace holding the `mapping(bytes32 => bool)` of enabled features.
+pub const FEATURES_NAMESPACE: &str = "cannon.router.features";
+
+/// Error raised when a selector's feature is disabled.
+const FEATURE_UNAVAILABLE_ERROR: &str = "FeatureUnavailable(bytes32 feature)";
+
+/// Functions implemented by the router itself to manage features.
+const FEATURE_FUNCTIONS: [&str; 2] = [
+    "function setFeatureEnabled(bytes32 feature, bool enabled) external",
+    "function isFeatureEnabled(bytes32 feature) external view returns (bool enabled)",
+];
+
+const FEATURE_EVENT: &str = "event FeatureToggled(bytes32 indexed feature, bool enabled)";
+
+/// A named group of selectors that can be enabled or disabled on-chain.
+///
+/// Features start disabled and are toggled with `setFeatureEnabled(keccak256(name), enabled)`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Feature {
+    pub name: String,
+    /// The modules and selectors gated by the feature.
+    #[serde(flatten)]
+    pub scope: Scope,
+}
+
+impl Feature {
+    /// The on-chain feature identifier, `keccak256(name)`.
+    pub fn id(&self) -> B256 {
+        keccak256(&self.name)
+    }
+}
+
+/// Map each gated selector to the indices of the features it belongs to.
+fn resolve_features(
+    features: &[Feature],
+    selectors: &HashMap<Selector, Module>,
+) -> Result<BTreeMap<Selector, Vec<usize>>> {
+    let mut gated = BTreeMap::<Selector, Vec<usize>>::new();
+
+    for (i, feature) in features.iter().enumerate() {
+        if feature.scope.is_empty() {
+            return Err(eyre!(
+                "Feature `{}` must be scoped to modules or selectors",
+                feature.name
+            ));
+        }
+
+        let owner = format!("Feature `{}`", feature.name);
+        for selector in feature.scope.resolve(&owner, selectors)? {
+            gated.entry(selector).or_default().push(i);
+        }
+    }
+
+    Ok(gated)
+}
+
+/// Add the feature management functions, event and errors to the router's interface.
+pub fn extend_abi(features: &[Feature], abi: &JsonAbi) -> Result<JsonAbi> {
+    let mut abi = abi.clone();
+    if features.is_empty() {
+        return Ok(abi);
+    }
+
+    for signature in FEATURE_FUNCTIONS {
+        let function = Function::parse(signature)?;
+        if abi.functions().any(|f| f.selector() == function.selector()) {
+            return Err(eyre!(
+                "Selector `{}` is reserved by the router's feature flags",
+                function.signature()
+            ));
+        }
+        abi.functions
+            .entry(function.name.clone())
+            .or_default()
+            .push(function);
+    }
+
+    let event = Event::parse(FEATURE_EVENT)?;
+    abi.events
+        .entry(event.name.clone())
+        .or_default()
+        .push(event);
+
+    for signature in [FEATURE_UNAVAILABLE_ERROR, UNAUTHORIZED_ERROR] {
+        let error = Error::parse(signature)?;
+        if !abi.errors().any(|e| e.selector() == error.selector()) {
+            abi.errors
+                .entry(error.name.clone())
+                .or_default()
+                .push(error);
+        }
+    }
+
+    Ok(abi)
+}
+
+/// Render the feature checks prepended to each gated `case` of the dispatch tree.
+pub fn render_feature_calls(
+    features: &[Feature],
+    selectors: &HashMap<Selector, Module>,
+) -> Result<HashMap<Selector, String>> {
+    Ok(resolve_features(features, selectors)?
+        .into_iter()
+        .map(|(selector, indices)| {
+            let calls = indices
+                .iter()
+                .map(|i| format!("feature{i}() "))
+                .collect::<String>();
+            (selector, calls)
+        })
+        .collect())
+}
+
+/// Render the Yul functions checking each feature is enabled.
+pub fn render_feature_functions(features: &[Feature]) -> Result<String> {
+    if features.is_empty() {
+        return Ok(String::new());
+    }
+
+    let unavailable = Error::parse(FEATURE_UNAVAILABLE_ERROR)?.selector();
+    let slot = erc7201_slot(FEATURES_NAMESPACE);
+
+    let mut lines = vec![];
+    for (i, feature) in features.iter().enumerate() {
+        let id = feature.id().encode_hex_with_prefix();
+        lines.push(format!(
+            "            function feature{i}() {{ // {}",
+            feature.name
+        ));
+        lines.push(format!("                mstore(0, {id})"));
+        lines.push(format!(
+            "                mstore(32, {})",
+            slot.encode_hex_with_prefix()
+        ));
+        lines.push("                if iszero(sload(keccak256(0, 64))) {".to_string());
+        lines.push(format!(
+            "                    mstore(0, shl(224, {})) // {FEATURE_UNAVAILABLE_ERROR}",
+            unavailable.encode_hex_with_prefix()
+        ));
+        lines.push(format!("                    mstore(4, {id})"));
+        lines.push("                    revert(0, 0x24)".to_string());
+        lines.push("                }".to_string());
+        lines.push("            }".to_string());
+        lines.push(String::new());
+    }
+
+    Ok(format!("{}\n", lines.join("\n")))
+}
+
+/// Render the router functions toggling and querying features.
+pub fn render_feature_admin(features: &[Feature], admin: Option<&Access>) -> Result<String> {
+    if features.is_empty() {
+        return Ok(String::new());
+    }
+
+    let admin = admin.ok_or_else(|| eyre!("Features require a `feature_admin`"))?;
+
+    let mut check = vec!["        assembly {".to_string()];
+    check.extend(render_access_check(
+        admin,
+        "            ",
+        "featureUnauthorized()",
+    )?);
+    check.push(String::new());
+    check.push("            function featureUnauthorized() {".to_string());
+    check.extend(render_unauthorized("                ")?);
+    check.push("            }".to_string());
+    check.push("        }".to_string());
+
+    let feature_ids = features
+        .iter()
+        .map(|f| format!("    // {}: {}", f.name, f.id()))
+        .collect::<Vec<String>>()
+        .join("\n");
+    let slot = erc7201_slot(FEATURES_NAMESPACE);
+
+    let content = include_str!("../../../assets/templates/FeatureAdminTemplate.sol");
+    let content = content
+        .replace("{features_slot}", &slot.encode_hex_with_prefix())
+        .replace("{features_namespace}", FEATURES_NAMESPACE)
+        .replace("{feature_ids}", &feature_ids)
+        .replace("{admin_check}", &check.join("\n"));
+
+    Ok(content)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::tests::{module, routed};
+    use super::*;
+
+    fn features(toml: &str) -> Vec<Feature> {
+        #[derive(Deserialize)]
+        struct Router {
+            features: Vec<Feature>,
+        }
+        toml::from_str::<Router>(toml).unwrap().features
+    }
+
+    fn features_without_scope() -> Vec<Feature> {
+        features(
+            r#"
+            [[features]]
+            name = "everything"
+            "#,
+        )
+    }
+
+    fn selectors() -> HashMap<Selector, Module> {
+        routed([
+            module("src/Vault.sol:Vault", "deposit(uint256)"),
+            module("src/Vault.sol:Vault", "withdraw(uint256)"),
+            module("src/Admin.sol:Admin", "pause()"),
+        ])
+    }
+
+    fn selector(signature: &str) -> Selector {
+        Function::parse(signature).unwrap().selector()
+    }
+
+    #[test]
+    fn gates_the_scoped_selectors() {
+        let features = features(
+            r#"
+            [[features]]
+            name = "vault"
+            modules = ["Vault"]
+
+            [[features]]
+            name = "withdrawals"
+            selectors = ["withdraw(uint256)"]
+            "#,
+        );
+        let calls = render_feature_calls(&features, &selectors()).unwrap();
+
+        assert_eq!(calls.len(), 2);
+        assert_eq!(calls[&selector("deposit(uint256)")], "feature0() ");
+        assert_eq!(
+            calls[&selector("withdraw(uint256)")],
+            "feature0() feature1() "
+        );
+
+        assert!(render_feature_calls(&features_without_scope(), &selectors()).is_err());
+    }
+
+    #[test]
+    fn checks_the_feature_id_in_the_namespaced_mapping() {
+        let features = features(
+            r#"
+            [[features]]
+            name = "vault"
+            modules = ["Vault"]
+            "#,
+        );
+        let rendered = render_feature_functions(&features).unwrap();
+        let id = keccak256("vault").encode_hex_with_prefix();
+
+        assert_eq!(features[0].id(), keccak256("vault"));
+        assert!(rendered.contains("            function feature0() { // vault"));
+        assert!(rendered.contains(&format!("mstore(0, {id})")));
+        assert!(rendered.contains(&format!(
+            "mstore(32, {})",
+            erc7201_slot(FEATURES_NAMESPACE).encode_hex_with_prefix()
+        )));
+        assert!(rendered.contains(&format!("mstore(4, {id})")));
+        assert_eq!(render_feature_functions(&[]).unwrap(), "");
+    }
+
+    #[test]
+    fn renders_the_admin_functions_behind_the_feature_admin() {
+        let features = features(
+            r#"
+            [[features]]
+            name = "vault"
+            modules = ["Vault"]
+            "#,
+        );
+        let admin = Access::Owner(
+            "0x1111111111111111111111111111111111111111"
+                .parse()
+                .unwrap(),
+        );
+
+        assert!(render_feature_admin(&features, None).is_err());
+        assert_eq!(render_feature_admin(&[], None).unwrap(), "");
+
+        let rendered = render_feature_admin(&features, Some(&admin)).unwrap();
+        assert!(rendered.contains(&format!("    // vault: {}", keccak256("vault"))));
+        assert!(rendered.contains(
+            "            if iszero(eq(caller(), 0x1111111111111111111111111111111111111111)) { featureUnauthorized() }"
+        ));
+        assert!(!rendered.contains("{features_slot}") && !rendered.contains("{admin_check}"));
+    }
+
+    #[test]
+    fn reserves_the_feature_selectors() {
+        let features = features_without_scope();
+
+        let abi = extend_abi(&features, &JsonAbi::new()).unwrap();
+        assert!(abi.function("setFeatureEnabled").is_some());
+        assert!(abi.function("isFeatureEnabled").is_some());
+        assert!(abi.event("FeatureToggled").is_some());
+        assert_eq!(abi.errors().count(), 2);
+
+        assert!(extend_abi(&features, &abi).is_err());
+        assert_eq!(extend_abi(&[], &abi).unwrap(), abi);
+    }
+}
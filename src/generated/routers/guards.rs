@@ -0,0 +1,400 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt,
+};
+
+use alloy_json_abi::{Error, Function, JsonAbi};
+use alloy_primitives::{hex::ToHexExt, Address, Selector, B256};
+use eyre::{eyre, Result};
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+
+use super::{scope::Scope, Module};
+
+/// Error raised by the router when a guard rejects the caller.
+pub const UNAUTHORIZED_ERROR: &str = "Unauthorized(address sender)";
+
+/// Access control enforced by the router before dispatching the scoped selectors.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Guard {
+    /// The modules and selectors the guard applies to.
+    #[serde(flatten)]
+    pub scope: Scope,
+    pub access: Access,
+}
+
+/// Who may call a guarded selector.
+///
+/// In TOML this is a single-key table, e.g. `access = { owner = "0x..." }`,
+/// `access = { role = { authority = "0x...", role = "0x..." } }` or
+/// `access = { allowlist = ["0x...", "0x..."] }`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Access {
+    /// Only the given address.
+    Owner(Address),
+    /// Callers for which `authority.hasRole(role, caller)` returns true.
+    Role { authority: Address, role: B256 },
+    /// Only the listed addresses.
+    Allowlist(Vec<Address>),
+}
+
+impl fmt::Display for Access {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Owner(owner) => write!(f, "owner {owner}"),
+            Self::Role { authority, role } => write!(f, "role {role} on {authority}"),
+            Self::Allowlist(callers) => write!(
+                f,
+                "allowlist [{}]",
+                callers.iter().map(|c| c.to_string()).join(", ")
+            ),
+        }
+    }
+}
+
+/// A routed selector and the guards protecting it, if any.
+#[derive(Debug, Clone, Serialize)]
+pub struct Permission {
+    pub selector: Selector,
+    pub contract_name: String,
+    pub function_name: String,
+    pub access: Vec<Access>,
+}
+
+/// Map each guarded selector to the indices of the guards applying to it.
+fn resolve_guards(
+    guards: &[Guard],
+    selectors: &HashMap<Selector, Module>,
+) -> Result<BTreeMap<Selector, Vec<usize>>> {
+    let mut guarded = BTreeMap::<Selector, Vec<usize>>::new();
+
+    for (i, guard) in guards.iter().enumerate() {
+        if guard.scope.is_empty() {
+            return Err(eyre!(
+                "Guard `{}` must be scoped to modules or selectors",
+                guard.access
+            ));
+        }
+
+        if guard.access == Access::Allowlist(vec![]) {
+            return Err(eyre!("Guard allowlist must not be empty"));
+        }
+
+        let owner = format!("Guard `{}`", guard.access);
+        for selector in guard.scope.resolve(&owner, selectors)? {
+            guarded.entry(selector).or_default().push(i);
+        }
+    }
+
+    Ok(guarded)
+}
+
+/// List every routed selector with the guards protecting it, sorted by module and function.
+pub fn permission_matrix(
+    guards: &[Guard],
+    selectors: &HashMap<Selector, Module>,
+) -> Result<Vec<Permission>> {
+    let guarded = resolve_guards(guards, selectors)?;
+
+    Ok(selectors
+        .values()
+        .sorted_by(|a, b| {
+            (&a.contract_name, &a.function_name, a.selector).cmp(&(
+                &b.contract_name,
+                &b.function_name,
+                b.selector,
+            ))
+        })
+        .map(|m| Permission {
+            selector: m.selector,
+            contract_name: m.contract_name.clone(),
+            function_name: m.function_name.clone(),
+            access: guarded
+                .get(&m.selector)
+                .map(|indices| indices.iter().map(|&i| guards[i].access.clone()).collect())
+                .unwrap_or_default(),
+        })
+        .collect())
+}
+
+/// Add the error raised by guards to the router's interface.
+pub fn extend_abi(guards: &[Guard], abi: &JsonAbi) -> Result<JsonAbi> {
+    let mut abi = abi.clone();
+    if !guards.is_empty() {
+        let error = Error::parse(UNAUTHORIZED_ERROR)?;
+        if !abi.errors().any(|e| e.selector() == error.selector()) {
+            abi.errors
+                .entry(error.name.clone())
+                .or_default()
+                .push(error);
+        }
+    }
+    Ok(abi)
+}
+
+/// Render the guard checks prepended to each guarded `case` of the dispatch tree.
+pub fn render_guard_calls(
+    guards: &[Guard],
+    selectors: &HashMap<Selector, Module>,
+) -> Result<HashMap<Selector, String>> {
+    Ok(resolve_guards(guards, selectors)?
+        .into_iter()
+        .map(|(selector, indices)| {
+            let calls = indices.iter().map(|i| format!("guard{i}() ")).join("");
+            (selector, calls)
+        })
+        .collect())
+}
+
+/// Render the Yul statements checking the caller against `access`, calling `unauthorized` on
+/// failure.
+pub fn render_access_check(
+    access: &Access,
+    indent: &str,
+    unauthorized: &str,
+) -> Result<Vec<String>> {
+    let mut lines = vec![];
+
+    match access {
+        Access::Owner(owner) => {
+            lines.push(format!(
+                "{indent}if iszero(eq(caller(), {})) {{ {unauthorized} }}",
+                owner.encode_hex_with_prefix()
+            ));
+        }
+        Access::Role { authority, role } => {
+            let has_role = Function::parse("hasRole(bytes32,address)")?;
+            lines.push(format!("{indent}let ptr := mload(0x40)"));
+            lines.push(format!(
+                "{indent}mstore(ptr, shl(224, {})) // hasRole(bytes32,address)",
+                has_role.selector().encode_hex_with_prefix()
+            ));
+            lines.push(format!(
+                "{indent}mstore(add(ptr, 4), {})",
+                role.encode_hex_with_prefix()
+            ));
+            lines.push(format!("{indent}mstore(add(ptr, 36), caller())"));
+            lines.push(format!(
+                "{indent}let success := staticcall(gas(), {}, ptr, 68, ptr, 32)",
+                authority.encode_hex_with_prefix()
+            ));
+            lines.push(format!(
+                "{indent}if iszero(and(success, and(gt(returndatasize(), 31), eq(mload(ptr), 1)))) {{ {unauthorized} }}"
+            ));
+        }
+        Access::Allowlist(callers) => {
+            lines.push(format!("{indent}switch caller()"));
+            for caller in callers.iter().unique() {
+                lines.push(format!(
+                    "{indent}case {} {{}}",
+                    caller.encode_hex_with_prefix()
+                ));
+            }
+            lines.push(format!("{indent}default {{ {unauthorized} }}"));
+        }
+    }
+
+    Ok(lines)
+}
+
+/// Render the Yul statements reverting with `Unauthorized(caller())`.
+pub fn render_unauthorized(indent: &str) -> Result<Vec<String>> {
+    let unauthorized = Error::parse(UNAUTHORIZED_ERROR)?.selector();
+
+    Ok(vec![
+        format!(
+            "{indent}mstore(0, shl(224, {})) // {UNAUTHORIZED_ERROR}",
+            unauthorized.encode_hex_with_prefix()
+        ),
+        format!("{indent}mstore(4, caller())"),
+        format!("{indent}revert(0, 0x24)"),
+    ])
+}
+
+/// Render the Yul functions implementing each guard.
+pub fn render_guard_functions(guards: &[Guard]) -> Result<String> {
+    if guards.is_empty() {
+        return Ok(String::new());
+    }
+
+    let mut lines = vec!["            function guardUnauthorized() {".to_string()];
+    lines.extend(render_unauthorized("                ")?);
+    lines.push("            }".to_string());
+
+    for (i, guard) in guards.iter().enumerate() {
+        lines.push(String::new());
+        lines.push(format!(
+            "            function guard{i}() {{ // {}",
+            guard.access
+        ));
+        lines.extend(render_access_check(
+            &guard.access,
+            "                ",
+            "guardUnauthorized()",
+        )?);
+        lines.push("            }".to_string());
+    }
+
+    Ok(format!("{}\n\n", lines.join("\n")))
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy_json_abi::Function;
+
+    use super::super::tests::{module, routed};
+    use super::*;
+
+    const OWNER: &str = "0x1111111111111111111111111111111111111111";
+    const AUTHORITY: &str = "0x2222222222222222222222222222222222222222";
+
+    fn guards(toml: &str) -> Vec<Guard> {
+        #[derive(Deserialize)]
+        struct Router {
+            guards: Vec<Guard>,
+        }
+        toml::from_str::<Router>(toml).unwrap().guards
+    }
+
+    fn selectors() -> HashMap<Selector, Module> {
+        routed([
+            module("src/Vault.sol:Vault", "deposit(uint256)"),
+            module("src/Vault.sol:Vault", "withdraw(uint256)"),
+            module("src/Admin.sol:Admin", "pause()"),
+        ])
+    }
+
+    fn selector(signature: &str) -> Selector {
+        Function::parse(signature).unwrap().selector()
+    }
+
+    #[test]
+    fn calls_every_guard_of_a_selector_in_order() {
+        let guards = guards(&format!(
+            r#"
+            [[guards]]
+            modules = ["Vault"]
+            access = {{ owner = "{OWNER}" }}
+
+            [[guards]]
+            selectors = ["withdraw(uint256)"]
+            access = {{ allowlist = ["{AUTHORITY}"] }}
+            "#
+        ));
+        let calls = render_guard_calls(&guards, &selectors()).unwrap();
+
+        assert_eq!(calls.len(), 2);
+        assert_eq!(calls[&selector("deposit(uint256)")], "guard0() ");
+        assert_eq!(calls[&selector("withdraw(uint256)")], "guard0() guard1() ");
+        assert!(!calls.contains_key(&selector("pause()")));
+    }
+
+    #[test]
+    fn renders_each_access_check() {
+        let guards = guards(&format!(
+            r#"
+            [[guards]]
+            modules = ["Admin"]
+            access = {{ owner = "{OWNER}" }}
+
+            [[guards]]
+            modules = ["Admin"]
+            access = {{ role = {{ authority = "{AUTHORITY}", role = "0x{}" }} }}
+
+            [[guards]]
+            modules = ["Admin"]
+            access = {{ allowlist = ["{OWNER}", "{AUTHORITY}", "{OWNER}"] }}
+            "#,
+            "01".repeat(32)
+        ));
+        let rendered = render_guard_functions(&guards).unwrap();
+        let unauthorized = Error::parse(UNAUTHORIZED_ERROR).unwrap().selector();
+
+        assert!(rendered.contains(&format!(
+            "mstore(0, shl(224, {})) // {UNAUTHORIZED_ERROR}",
+            unauthorized.encode_hex_with_prefix()
+        )));
+        assert!(rendered.contains(&format!(
+            "function guard0() {{ // owner {OWNER}\n                if iszero(eq(caller(), {OWNER})) {{ guardUnauthorized() }}"
+        )));
+        assert!(rendered.contains(&format!(
+            "let success := staticcall(gas(), {AUTHORITY}, ptr, 68, ptr, 32)"
+        )));
+        assert!(rendered.contains(&format!("mstore(add(ptr, 4), 0x{})", "01".repeat(32))));
+        assert_eq!(rendered.matches(&format!("case {OWNER} {{}}")).count(), 1);
+        assert!(rendered.contains(&format!("case {AUTHORITY} {{}}")));
+        assert_eq!(render_guard_functions(&[]).unwrap(), "");
+    }
+
+    #[test]
+    fn rejects_unscoped_guards_and_empty_allowlists() {
+        let unscoped = guards(&format!(
+            r#"
+            [[guards]]
+            access = {{ owner = "{OWNER}" }}
+            "#
+        ));
+        let empty = guards(
+            r#"
+            [[guards]]
+            modules = ["Vault"]
+            access = { allowlist = [] }
+            "#,
+        );
+
+        assert!(render_guard_calls(&unscoped, &selectors()).is_err());
+        assert!(render_guard_calls(&empty, &selectors()).is_err());
+    }
+
+    #[test]
+    fn lists_permissions_by_module_and_function() {
+        let guards = guards(&format!(
+            r#"
+            [[guards]]
+            selectors = ["withdraw(uint256)"]
+            access = {{ owner = "{OWNER}" }}
+            "#
+        ));
+        let matrix = permission_matrix(&guards, &selectors()).unwrap();
+
+        let rows = matrix
+            .iter()
+            .map(|p| {
+                (
+                    p.contract_name.as_str(),
+                    p.function_name.as_str(),
+                    p.access.len(),
+                )
+            })
+            .collect::<Vec<(&str, &str, usize)>>();
+        assert_eq!(
+            rows,
+            [
+                ("Admin", "pause", 0),
+                ("Vault", "deposit", 0),
+                ("Vault", "withdraw", 1)
+            ]
+        );
+        assert_eq!(matrix[2].access, [Access::Owner(OWNER.parse().unwrap())]);
+    }
+
+    #[test]
+    fn adds_the_unauthorized_error_once() {
+        let guards = guards(&format!(
+            r#"
+            [[guards]]
+            modules = ["Admin"]
+            access = {{ owner = "{OWNER}" }}
+            "#
+        ));
+
+        let abi = extend_abi(&guards, &JsonAbi::new()).unwrap();
+        assert_eq!(abi.errors().count(), 1);
+        assert_eq!(extend_abi(&guards, &abi).unwrap().errors().count(), 1);
+        assert_eq!(
+            extend_abi(&[], &JsonAbi::new()).unwrap().errors().count(),
+            0
+        );
+    }
+}
@@ -0,0 +1,316 @@
+use std::{collections::HashMap, path::Path};
+
+use alloy_primitives::{hex::ToHexExt, Selector};
+use eyre::{eyre, Result};
+use foundry_compilers::{
+    artifacts::ast::{Node, NodeType},
+    Project, ProjectCompileOutput,
+};
+use itertools::Itertools;
+use serde::Deserialize;
+
+use super::{find_artifacts, output::relative_import, scope::Scope, Module, ModuleEntry};
+
+/// A library function the router calls before dispatching, optionally scoped to a subset of
+/// modules or selectors.
+///
+/// The function must be `internal`, take the `bytes4` selector being routed and return nothing,
+/// e.g. `function beforeCall(bytes4 sig) internal`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Hook {
+    /// The hook function as `<path>:<Library>.<function>`.
+    pub before: String,
+    /// Only run the hook for these modules and selectors.
+    #[serde(flatten)]
+    pub scope: Scope,
+}
+
+impl Hook {
+    /// The hook library as `<path>:<Library>`, suitable for `identify_sources`.
+    pub fn library(&self) -> Result<String> {
+        let (path, library, _) = self.target()?;
+        Ok(format!("{path}:{library}"))
+    }
+
+    fn target(&self) -> Result<(String, String, String)> {
+        let invalid = || {
+            eyre!(
+                "Invalid hook `{}`, expected `<path>:<Library>.<function>`",
+                self.before
+            )
+        };
+
+        let (path, function) = self.before.rsplit_once(':').ok_or_else(invalid)?;
+        let (library, function) = function.split_once('.').ok_or_else(invalid)?;
+
+        Ok((path.to_string(), library.to_string(), function.to_string()))
+    }
+
+    /// Resolve the selectors this hook applies to, `None` meaning every selector.
+    fn resolve_scope(
+        &self,
+        selectors: &HashMap<Selector, Module>,
+    ) -> Result<Option<Vec<Selector>>> {
+        if self.scope.is_empty() {
+            return Ok(None);
+        }
+
+        let owner = format!("Hook `{}`", self.before);
+        self.scope.resolve(&owner, selectors).map(Some)
+    }
+}
+
+/// Check every hook resolves to an internal library function taking a `bytes4`.
+///
+/// Requires the AST to be part of the compiler's output.
+pub fn validate_hooks(
+    project: &Project,
+    output: &ProjectCompileOutput,
+    hooks: &[Hook],
+) -> Result<()> {
+    for hook in hooks {
+        let (_, library, function) = hook.target()?;
+        let artifacts = find_artifacts(project, output, vec![ModuleEntry::new(hook.library()?)])?;
+        let (_, artifact) = &artifacts[0];
+
+        let ast = artifact
+            .ast
+            .as_ref()
+            .ok_or_else(|| eyre!("No AST found for hook library `{library}`, enable `ast`"))?;
+
+        let contract = ast
+            .nodes
+            .iter()
+            .filter(|n| n.node_type == NodeType::ContractDefinition)
+            .find(|n| attribute(n, "name") == Some(library.as_str()))
+            .ok_or_else(|| eyre!("Hook library `{library}` not found"))?;
+
+        if attribute(contract, "contractKind") != Some("library") {
+            return Err(eyre!("Hook `{}` must be defined in a library", hook.before));
+        }
+
+        let definition = contract
+            .nodes
+            .iter()
+            .filter(|n| n.node_type == NodeType::FunctionDefinition)
+            .filter(|n| attribute(n, "name") == Some(function.as_str()))
+            .find(|n| {
+                let parameters = parameter_types(n, "parameters");
+                let returns = parameter_types(n, "returnParameters");
+                attribute(n, "visibility") == Some("internal")
+                    && parameters == ["bytes4"]
+                    && returns.is_empty()
+            });
+
+        if definition.is_none() {
+            return Err(eyre!(
+                "Hook `{}` must be declared as `function {function}(bytes4) internal`",
+                hook.before
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+fn attribute<'a>(node: &'a Node, name: &str) -> Option<&'a str> {
+    node.other.get(name).and_then(|v| v.as_str())
+}
+
+fn parameter_types(node: &Node, name: &str) -> Vec<String> {
+    node.other
+        .get(name)
+        .and_then(|p| p.get("parameters"))
+        .and_then(|p| p.as_array())
+        .map(|parameters| {
+            parameters
+                .iter()
+                .filter_map(|p| p.get("typeDescriptions")?.get("typeString")?.as_str())
+                .map(|t| t.to_string())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Render the hook library imports, relative to `output_dir` when the router's location is known.
+pub fn render_imports(hooks: &[Hook], root: &Path, output_dir: Option<&Path>) -> Result<String> {
+    let imports = hooks
+        .iter()
+        .map(|hook| {
+            let (path, library, _) = hook.target()?;
+            // Remapped paths resolve from anywhere, only project files are made relative.
+            let path = match output_dir {
+                Some(dir) if root.join(&path).is_file() => relative_import(dir, &root.join(&path)),
+                _ => path,
+            };
+            Ok(format!("import {{{library}}} from \"{path}\";"))
+        })
+        .collect::<Result<Vec<String>>>()?
+        .into_iter()
+        .unique()
+        .collect::<Vec<String>>();
+
+    if imports.is_empty() {
+        return Ok(String::new());
+    }
+
+    Ok(format!("{}\n\n", imports.join("\n")))
+}
+
+/// Render the hook calls made before the router dispatches.
+pub fn render_hooks(hooks: &[Hook], selectors: &HashMap<Selector, Module>) -> Result<String> {
+    if hooks.is_empty() {
+        return Ok(String::new());
+    }
+
+    let mut lines = vec!["        // Pre-dispatch hooks".to_string()];
+
+    for hook in hooks {
+        let (_, library, function) = hook.target()?;
+        let call = format!("{library}.{function}(sig4);");
+
+        match hook.resolve_scope(selectors)? {
+            None => lines.push(format!("        {call}")),
+            Some(scope) => {
+                lines.push("        {".to_string());
+                lines.push("            bool hooked;".to_string());
+                lines.push("            assembly {".to_string());
+                lines.push("                switch shr(224, sig4)".to_string());
+                for selector in scope {
+                    lines.push(format!(
+                        "                case {} {{ hooked := 1 }}",
+                        selector.encode_hex_with_prefix()
+                    ));
+                }
+                lines.push("            }".to_string());
+                lines.push("            if (hooked) {".to_string());
+                lines.push(format!("                {call}"));
+                lines.push("            }".to_string());
+                lines.push("        }".to_string());
+            }
+        }
+    }
+
+    Ok(format!("{}\n\n", lines.join("\n")))
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy_json_abi::Function;
+
+    use super::super::tests::{module, routed};
+    use super::*;
+
+    fn hook(toml: &str) -> Hook {
+        toml::from_str(toml).unwrap()
+    }
+
+    fn selectors() -> HashMap<Selector, Module> {
+        routed([
+            module("src/Vault.sol:Vault", "deposit(uint256)"),
+            module("src/Vault.sol:Vault", "withdraw(uint256)"),
+            module("src/Token.sol:Token", "transfer(address,uint256)"),
+        ])
+    }
+
+    #[test]
+    fn renders_nothing_without_hooks() {
+        assert_eq!(render_hooks(&[], &selectors()).unwrap(), "");
+        assert_eq!(render_imports(&[], Path::new("."), None).unwrap(), "");
+    }
+
+    #[test]
+    fn renders_unscoped_hooks_in_order() {
+        let hooks = [
+            hook(r#"before = "src/Hooks.sol:Hooks.first""#),
+            hook(r#"before = "src/Hooks.sol:Hooks.second""#),
+        ];
+
+        assert_eq!(
+            render_hooks(&hooks, &selectors()).unwrap(),
+            "        // Pre-dispatch hooks\n        Hooks.first(sig4);\n        Hooks.second(sig4);\n\n"
+        );
+        assert_eq!(
+            render_imports(&hooks, Path::new("."), None).unwrap(),
+            "import {Hooks} from \"src/Hooks.sol\";\n\n"
+        );
+    }
+
+    #[test]
+    fn renders_scoped_hooks_as_a_selector_switch() {
+        let hooks = [hook(
+            r#"
+            before = "src/Hooks.sol:Hooks.before"
+            modules = ["Vault"]
+            selectors = ["transfer(address,uint256)"]
+            "#,
+        )];
+        let rendered = render_hooks(&hooks, &selectors()).unwrap();
+
+        let cases = rendered
+            .lines()
+            .filter(|l| l.trim_start().starts_with("case "))
+            .collect::<Vec<&str>>();
+        assert_eq!(cases.len(), 3);
+        assert_eq!(cases, cases.iter().copied().sorted().collect::<Vec<&str>>());
+        for signature in [
+            "deposit(uint256)",
+            "withdraw(uint256)",
+            "transfer(address,uint256)",
+        ] {
+            let selector = Function::parse(signature).unwrap().selector();
+            assert!(rendered.contains(&format!(
+                "case {} {{ hooked := 1 }}",
+                selector.encode_hex_with_prefix()
+            )));
+        }
+        assert!(rendered.contains("            if (hooked) {\n                Hooks.before(sig4);"));
+    }
+
+    #[test]
+    fn rejects_scopes_outside_the_router() {
+        let unknown_module = hook(
+            r#"
+            before = "src/Hooks.sol:Hooks.before"
+            modules = ["Missing"]
+            "#,
+        );
+        let unrouted_selector = hook(
+            r#"
+            before = "src/Hooks.sol:Hooks.before"
+            selectors = ["approve(address,uint256)"]
+            "#,
+        );
+
+        assert!(render_hooks(&[unknown_module], &selectors()).is_err());
+        assert!(render_hooks(&[unrouted_selector], &selectors()).is_err());
+    }
+
+    #[test]
+    fn rejects_invalid_targets() {
+        assert!(hook(r#"before = "Hooks.before""#).library().is_err());
+        assert!(hook(r#"before = "src/Hooks.sol:Hooks""#).library().is_err());
+        assert_eq!(
+            hook(r#"before = "src/Hooks.sol:Hooks.before""#)
+                .library()
+                .unwrap(),
+            "src/Hooks.sol:Hooks"
+        );
+    }
+
+    #[test]
+    fn renders_project_imports_relative_to_the_output_dir() {
+        let root = std::env::temp_dir().join("cannon-rs-hook-imports");
+        std::fs::create_dir_all(root.join("src")).unwrap();
+        std::fs::write(root.join("src/Hooks.sol"), "").unwrap();
+        let hooks = [
+            hook(r#"before = "src/Hooks.sol:Hooks.before""#),
+            hook(r#"before = "lib/hooks/Remapped.sol:Remapped.before""#),
+        ];
+
+        assert_eq!(
+            render_imports(&hooks, &root, Some(&root.join("src/generated"))).unwrap(),
+            "import {Hooks} from \"../Hooks.sol\";\nimport {Remapped} from \"lib/hooks/Remapped.sol\";\n\n"
+        );
+    }
+}
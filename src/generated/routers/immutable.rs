@@ -1,16 +1,21 @@
-use crate::generated::routers::utils::{to_constant_case, to_lower_camel_case};
 use alloy_json_abi::JsonAbi;
-use alloy_primitives::hex::ToHexExt;
 use eyre::Result;
 use foundry_compilers::{Project, ProjectCompileOutput};
 
-use super::{render_modules_with_template, Module};
+use super::{
+    render_modules_with_template, unknown::UnknownSelector, DispatchOptions, Module, ModuleEntry,
+};
+
+/// Identifiers the router declares next to the module immutables.
+pub const ROUTER_IDENTIFIERS: &[&str] = &["FEATURES_SLOT", "Modules"];
 
 pub fn generate_router(
     project: &Project,
     output: &ProjectCompileOutput,
     router_name: String,
-    module_names: Vec<String>,
+    module_names: Vec<ModuleEntry>,
+    unknown_selector: &UnknownSelector,
+    dispatch: &DispatchOptions,
 ) -> Result<String> {
     super::generate_router(
         project,
@@ -19,49 +24,49 @@ pub fn generate_router(
         module_names,
         None,
         None,
-        &|m: &Module| {
-            format!(
-                "case {} {{ result := {} }} // {}.{}()",
-                m.selector.encode_hex_with_prefix(),
-                m.contract_identifier,
-                m.contract_name,
-                m.function_name
-            )
+        ROUTER_IDENTIFIERS,
+        dispatch,
+        &|m: &Module| format!("result := {}", m.contract_identifier),
+        &|router_name: &String, modules: &Vec<Module>, abi: &JsonAbi| {
+            template(router_name, modules, abi, unknown_selector)
         },
-        &template,
     )
 }
 
-fn template(router_name: &String, modules: &Vec<Module>, abi: &JsonAbi) -> Result<String> {
+fn template(
+    router_name: &String,
+    modules: &Vec<Module>,
+    abi: &JsonAbi,
+    unknown_selector: &UnknownSelector,
+) -> Result<String> {
+    let abi = unknown_selector.extend_abi(abi)?;
     let interface = abi.to_sol(format!("I{router_name}").as_str(), None);
 
     let module_lookup = render_modules_with_template(modules, &|m| {
-        format!(
-            "    address immutable internal {};",
-            to_constant_case(&m.contract_name)
-        )
+        format!("    address immutable internal {};", m.constant_name)
     });
     let resolver = render_modules_with_template(modules, &|m| {
         format!(
             "        if (implementation == {}) return {};",
-            m.contract_identifier,
-            to_constant_case(&m.contract_name)
+            m.contract_identifier, m.constant_name
         )
     });
-    let constructor_args = render_modules_with_template(modules, &|m| {
-        format!("address {}", to_lower_camel_case(&m.contract_name))
-    });
+    let constructor_args =
+        render_modules_with_template(modules, &|m| format!("address {}", m.variable_name));
     let immutables = render_modules_with_template(modules, &|m| {
-        format!(
-            "        {} = $.{};",
-            to_constant_case(&m.contract_name),
-            to_lower_camel_case(&m.contract_name)
-        )
+        format!("        {} = $.{};", m.constant_name, m.variable_name)
     });
     let struct_str = render_modules_with_template(modules, &|m| {
-        format!("        address {};", to_lower_camel_case(&m.contract_name))
+        format!("        address {};", m.variable_name)
     });
 
+    let unknown_selector_error = unknown_selector.render_declaration()?;
+    let unknown_selector_fallback = unknown_selector.render_fallback(
+        modules,
+        |m| m.constant_name.clone(),
+        |target| format!("implementation_address = {target};"),
+    )?;
+
     // Create the router file content.
     let router_content = include_str!("../../../assets/templates/ImmutableRouterTemplate.sol");
     let router_content = router_content
@@ -71,7 +76,9 @@ fn template(router_name: &String, modules: &Vec<Module>, abi: &JsonAbi) -> Resul
         .replace("{constructor_args}", &constructor_args)
         .replace("{immutables}", &immutables)
         .replace("{struct}", &struct_str)
-        .replace("{modules}", &module_lookup);
+        .replace("{modules}", &module_lookup)
+        .replace("{unknown_selector_error}\n", &unknown_selector_error)
+        .replace("{unknown_selector}", &unknown_selector_fallback);
 
     Ok(router_content)
 }
@@ -0,0 +1,116 @@
+use std::collections::HashMap;
+
+use alloy_primitives::{keccak256, Address, Selector, B256};
+use eyre::Result;
+use foundry_compilers::{Project, ProjectCompileOutput};
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+
+use super::{
+    build_binary_tree, collect_sources, find_artifacts, pipeline::Flavor, tree_depths, ModuleEntry,
+};
+
+/// A routed selector and where the router sends it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SelectorInfo {
+    pub selector: Selector,
+    pub signature: String,
+    pub module: String,
+    pub identifier: String,
+    pub address: Option<Address>,
+    /// Number of binary splits the dispatch tree takes before reaching the selector's `switch`.
+    pub depth: usize,
+}
+
+/// A module of the router.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModuleInfo {
+    pub name: String,
+    pub identifier: String,
+    pub address: Option<Address>,
+    /// `keccak256` of the module's creation bytecode, `None` when it needs linking.
+    pub bytecode_hash: Option<B256>,
+    pub selectors: usize,
+}
+
+/// The routed surface of a router, as computed by `collect_sources`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RouterInfo {
+    pub name: String,
+    pub modules: Vec<ModuleInfo>,
+    pub selectors: Vec<SelectorInfo>,
+    pub max_depth: usize,
+}
+
+/// Describe a router without rendering or writing it.
+pub fn inspect_router(
+    project: &Project,
+    output: &ProjectCompileOutput,
+    router_name: String,
+    module_names: Vec<ModuleEntry>,
+    flavor: Flavor,
+) -> Result<RouterInfo> {
+    let bytecode_hashes = find_artifacts(project, output, module_names.clone())?
+        .into_iter()
+        .map(|(name, artifact)| {
+            let hash = artifact
+                .bytecode
+                .as_ref()
+                .and_then(|b| b.bytes())
+                .map(keccak256);
+            (name, hash)
+        })
+        .collect::<HashMap<String, Option<B256>>>();
+
+    // Module addresses are only known ahead of deployment for CREATE2 flavors.
+    let (deployer, salt) = flavor.create2().unzip();
+    let (selectors, _) = collect_sources(
+        project,
+        output,
+        module_names,
+        deployer,
+        salt,
+        flavor.router_identifiers(),
+    )?;
+
+    let tree = build_binary_tree(selectors.keys().cloned().collect());
+    let depths = tree_depths(&tree);
+
+    let selector_infos = selectors
+        .values()
+        .sorted_by(|a, b| (&a.contract_name, a.selector).cmp(&(&b.contract_name, b.selector)))
+        .map(|m| SelectorInfo {
+            selector: m.selector,
+            signature: m.signature.clone(),
+            module: m.contract_name.clone(),
+            identifier: m.contract_identifier.clone(),
+            address: m.address,
+            depth: depths.get(&m.selector).copied().unwrap_or_default(),
+        })
+        .collect::<Vec<SelectorInfo>>();
+
+    let modules = selectors
+        .values()
+        .into_group_map_by(|m| m.contract_name.clone())
+        .into_iter()
+        .sorted_by(|(a, _), (b, _)| a.cmp(b))
+        .map(|(name, functions)| ModuleInfo {
+            identifier: functions[0].contract_identifier.clone(),
+            address: functions[0].address,
+            bytecode_hash: bytecode_hashes.get(&name).copied().flatten(),
+            selectors: functions.len(),
+            name,
+        })
+        .collect::<Vec<ModuleInfo>>();
+
+    Ok(RouterInfo {
+        name: router_name,
+        modules,
+        max_depth: selector_infos
+            .iter()
+            .map(|s| s.depth)
+            .max()
+            .unwrap_or_default(),
+        selectors: selector_infos,
+    })
+}
@@ -0,0 +1,994 @@
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    fmt,
+};
+
+use alloy_primitives::{B256, U256};
+use eyre::{eyre, Result};
+use foundry_compilers::{Project, ProjectCompileOutput};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use super::{is_matching_path, ModuleEntry};
+
+/// How a lint rule is enforced.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Severity {
+    /// The rule is not checked.
+    Allow,
+    /// Findings are reported as warnings.
+    Warn,
+    /// Findings fail generation.
+    Deny,
+}
+
+/// A delegatecall-safety rule checked on every module.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Rule {
+    /// A constructor writes storage, which only exists in the module's own account.
+    ConstructorStorageWrite,
+    /// `selfdestruct` would run in the router's context.
+    Selfdestruct,
+    /// A mutable state variable initialized at declaration, i.e. by the constructor.
+    StateInitializer,
+    /// `delegatecall` to a target that is neither constant nor immutable.
+    UntrustedDelegatecall,
+}
+
+const RULES: [Rule; 4] = [
+    Rule::ConstructorStorageWrite,
+    Rule::Selfdestruct,
+    Rule::StateInitializer,
+    Rule::UntrustedDelegatecall,
+];
+
+impl fmt::Display for Rule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Self::ConstructorStorageWrite => "constructor_storage_write",
+            Self::Selfdestruct => "selfdestruct",
+            Self::StateInitializer => "state_initializer",
+            Self::UntrustedDelegatecall => "untrusted_delegatecall",
+        };
+        f.write_str(name)
+    }
+}
+
+/// Severity of each lint rule, every rule warns by default.
+///
+/// In TOML this is a table under the router, e.g.
+/// `lint = { state_initializer = "allow", untrusted_delegatecall = "deny" }`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(default)]
+pub struct LintOptions {
+    pub constructor_storage_write: Severity,
+    pub selfdestruct: Severity,
+    pub state_initializer: Severity,
+    pub untrusted_delegatecall: Severity,
+}
+
+impl Default for LintOptions {
+    fn default() -> Self {
+        Self {
+            constructor_storage_write: Severity::Warn,
+            selfdestruct: Severity::Warn,
+            state_initializer: Severity::Warn,
+            untrusted_delegatecall: Severity::Warn,
+        }
+    }
+}
+
+impl LintOptions {
+    pub fn severity(&self, rule: Rule) -> Severity {
+        match rule {
+            Rule::ConstructorStorageWrite => self.constructor_storage_write,
+            Rule::Selfdestruct => self.selfdestruct,
+            Rule::StateInitializer => self.state_initializer,
+            Rule::UntrustedDelegatecall => self.untrusted_delegatecall,
+        }
+    }
+
+    /// Whether any rule is checked, requiring the AST.
+    pub fn is_enabled(&self) -> bool {
+        RULES
+            .iter()
+            .any(|rule| self.severity(*rule) != Severity::Allow)
+    }
+}
+
+/// A rule violation in a module.
+#[derive(Debug, Clone, Serialize)]
+pub struct Finding {
+    pub rule: Rule,
+    pub severity: Severity,
+    pub module: String,
+    pub message: String,
+}
+
+impl fmt::Display for Finding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} [{}]: {}", self.module, self.rule, self.message)
+    }
+}
+
+/// Check the modules against the enabled rules.
+///
+/// Each module is checked along with the contracts it inherits from, resolved in the module's own
+/// build. Requires the AST to be part of the compiler's output.
+pub fn lint_modules(
+    project: &Project,
+    output: &ProjectCompileOutput,
+    module_names: Vec<ModuleEntry>,
+    options: &LintOptions,
+) -> Result<Vec<Finding>> {
+    if !options.is_enabled() {
+        return Ok(vec![]);
+    }
+
+    let mut findings = vec![];
+    for (name, linearized) in linearized_modules(project, output, &module_names)? {
+        let linearized = linearized.iter().collect::<Vec<&Value>>();
+        findings.extend(lint_contract(&name, &linearized, options));
+    }
+
+    Ok(findings)
+}
+
+/// Fail if a module writes one of the reserved slots, e.g. the router's own EIP-1967 slots.
+///
+/// Writes are `sstore` in inline assembly and OpenZeppelin's `StorageSlot.get*Slot` getters, to a
+/// number literal or a constant initialized with one. Slots computed at runtime and writes made
+/// from libraries are not followed. Requires the AST to be part of the compiler's output.
+pub fn check_slot_writes(
+    project: &Project,
+    output: &ProjectCompileOutput,
+    module_names: Vec<ModuleEntry>,
+    reserved: &[(&str, B256)],
+) -> Result<()> {
+    let mut writes = vec![];
+    for (name, linearized) in linearized_modules(project, output, &module_names)? {
+        let linearized = linearized.iter().collect::<Vec<&Value>>();
+        writes.extend(slot_writes(&name, &linearized, reserved));
+    }
+
+    if !writes.is_empty() {
+        return Err(eyre!(
+            "Modules write storage reserved by the router:\n  {}",
+            writes.join("\n  ")
+        ));
+    }
+
+    Ok(())
+}
+
+/// Each module with the contracts it inherits from, in linearization order.
+fn linearized_modules(
+    project: &Project,
+    output: &ProjectCompileOutput,
+    module_names: &[ModuleEntry],
+) -> Result<Vec<(String, Vec<Value>)>> {
+    // Base contracts may be defined in other sources, index every contract of the output by
+    // build as AST ids are only unique within a compiler run.
+    let mut contracts = HashMap::new();
+    for (id, artifact) in output.artifact_ids() {
+        if let Some(ast) = &artifact.ast {
+            let ast = serde_json::to_value(ast)?;
+            visit(&ast, &mut |node| {
+                if node_type(node) == Some("ContractDefinition") {
+                    if let Some(node_id) = node.get("id").and_then(Value::as_i64) {
+                        contracts
+                            .entry((id.build_id.clone(), node_id))
+                            .or_insert_with(|| node.clone());
+                    }
+                }
+            });
+        }
+    }
+
+    let mut modules = vec![];
+    for module in module_names {
+        let target = module.info();
+        let name = module.name();
+
+        let (id, artifact) = output
+            .artifact_ids()
+            .find(|(id, _)| {
+                id.name == target.name
+                    && target
+                        .path
+                        .as_deref()
+                        .is_some_and(|path| is_matching_path(project, &id.source, path))
+            })
+            .ok_or_else(|| eyre!("Module `{module}` not found"))?;
+        let ast = artifact
+            .ast
+            .as_ref()
+            .ok_or_else(|| eyre!("No AST found for module `{name}`, enable `ast`"))?;
+        let ast = serde_json::to_value(ast)?;
+
+        let module = ast
+            .get("nodes")
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+            .filter(|n| node_type(n) == Some("ContractDefinition"))
+            .find(|n| string(n, "name") == Some(target.name.as_str()))
+            .ok_or_else(|| eyre!("Module `{name}` not found in its AST"))?;
+
+        let linearized = module
+            .get("linearizedBaseContracts")
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+            .filter_map(Value::as_i64)
+            .filter_map(|node_id| contracts.get(&(id.build_id.clone(), node_id)).cloned())
+            .collect::<Vec<Value>>();
+
+        modules.push((name, linearized));
+    }
+
+    Ok(modules)
+}
+
+/// Check a module given with the contracts it inherits from, in linearization order.
+fn lint_contract(name: &str, linearized: &[&Value], options: &LintOptions) -> Vec<Finding> {
+    let mut findings = vec![];
+    let mut report = |rule: Rule, message: String| {
+        let severity = options.severity(rule);
+        if severity != Severity::Allow {
+            findings.push(Finding {
+                rule,
+                severity,
+                module: name.to_string(),
+                message,
+            });
+        }
+    };
+
+    let variables = state_variables(linearized);
+    let mutable = variables
+        .iter()
+        .filter(|(_, (_, mutability))| *mutability == "mutable")
+        .map(|(id, _)| *id)
+        .collect::<HashSet<i64>>();
+
+    for contract in linearized {
+        let contract_name = string(contract, "name").unwrap_or_default();
+
+        for node in children(contract) {
+            match node_type(node) {
+                Some("VariableDeclaration") => {
+                    let label = string(node, "name").unwrap_or_default();
+                    let initialized = node.get("value").is_some_and(|v| !v.is_null());
+                    if string(node, "mutability") == Some("mutable") && initialized {
+                        report(
+                            Rule::StateInitializer,
+                            format!("`{contract_name}.{label}` is initialized at declaration"),
+                        );
+                    }
+                }
+                Some("FunctionDefinition" | "ModifierDefinition") => {
+                    let function = match string(node, "kind") {
+                        Some("constructor") => "constructor".to_string(),
+                        _ => string(node, "name").unwrap_or_default().to_string(),
+                    };
+                    let location = format!("{contract_name}.{function}");
+                    let Some(body) = node.get("body") else {
+                        continue;
+                    };
+
+                    visit(body, &mut |n| {
+                        if is_selfdestruct(n) {
+                            report(
+                                Rule::Selfdestruct,
+                                format!("`{location}` calls `selfdestruct`"),
+                            );
+                        }
+                        if is_untrusted_delegatecall(n, &variables) {
+                            report(
+                                Rule::UntrustedDelegatecall,
+                                format!(
+                                    "`{location}` delegatecalls a target that is neither \
+                                     constant nor immutable"
+                                ),
+                            );
+                        }
+                    });
+                }
+                _ => {}
+            }
+        }
+    }
+
+    let definitions = definitions(linearized);
+    for contract in linearized {
+        let contract_name = string(contract, "name").unwrap_or_default();
+        let location = format!("{contract_name}.constructor");
+
+        for constructor in children(contract).filter(|n| string(n, "kind") == Some("constructor")) {
+            for (callee, write) in
+                constructor_writes(constructor, &definitions, &mutable, &variables)
+            {
+                let message = match callee {
+                    Some(callee) => format!("`{location}` writes {write} through `{callee}`"),
+                    None => format!("`{location}` writes {write}"),
+                };
+                report(Rule::ConstructorStorageWrite, message);
+            }
+        }
+    }
+
+    findings
+}
+
+/// The storage writes of a constructor, following the modifiers and internal functions it calls,
+/// with the function or modifier they happen in when not the constructor itself.
+///
+/// Calls resolve to the declaration the compiler saw, overrides of virtual functions are not
+/// followed. Any `sstore` is a write, whatever the slot.
+fn constructor_writes<'a>(
+    constructor: &'a Value,
+    definitions: &HashMap<i64, (&'a str, &'a Value)>,
+    mutable: &HashSet<i64>,
+    variables: &HashMap<i64, (&'a str, &str)>,
+) -> Vec<(Option<String>, String)> {
+    let mut writes = vec![];
+    let mut seen = HashSet::new();
+    let mut pending = VecDeque::from([(None, constructor)]);
+
+    while let Some((callee, node)) = pending.pop_front() {
+        let mut called = node
+            .get("modifiers")
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+            .filter_map(|m| {
+                m.get("modifierName")?
+                    .get("referencedDeclaration")?
+                    .as_i64()
+            })
+            .collect::<Vec<i64>>();
+
+        if let Some(body) = node.get("body") {
+            visit(body, &mut |n| {
+                if let Some(label) = written_state_variable(n, mutable, variables) {
+                    writes.push((callee.clone(), format!("`{label}`")));
+                }
+                if node_type(n) == Some("YulFunctionCall")
+                    && n.get("functionName").and_then(|f| string(f, "name")) == Some("sstore")
+                {
+                    writes.push((callee.clone(), "storage with `sstore`".to_string()));
+                }
+                if node_type(n) == Some("FunctionCall") {
+                    called.extend(
+                        n.get("expression")
+                            .and_then(|e| e.get("referencedDeclaration")?.as_i64()),
+                    );
+                }
+            });
+        }
+
+        for id in called {
+            let Some((contract_name, definition)) = definitions.get(&id) else {
+                continue;
+            };
+            if seen.insert(id) {
+                let name = string(definition, "name").unwrap_or_default();
+                pending.push_back((Some(format!("{contract_name}.{name}")), *definition));
+            }
+        }
+    }
+
+    writes
+}
+
+/// Fail on findings of denied rules, returning the others as warnings.
+pub fn check_findings(findings: Vec<Finding>) -> Result<Vec<String>> {
+    let (denied, warned): (Vec<Finding>, Vec<Finding>) = findings
+        .into_iter()
+        .partition(|f| f.severity == Severity::Deny);
+
+    if !denied.is_empty() {
+        return Err(eyre!(
+            "Modules are unsafe behind a delegatecall router:\n  {}\n\
+             Set the rules' severity in the router's `lint` table to allow them",
+            denied
+                .iter()
+                .map(|f| f.to_string())
+                .collect::<Vec<String>>()
+                .join("\n  ")
+        ));
+    }
+
+    Ok(warned.iter().map(|f| f.to_string()).collect())
+}
+
+/// Describe the writes of a module, given with the contracts it inherits from, to the reserved
+/// slots.
+fn slot_writes(name: &str, linearized: &[&Value], reserved: &[(&str, B256)]) -> Vec<String> {
+    let constants = linearized
+        .iter()
+        .flat_map(|c| children(c))
+        .filter(|n| node_type(n) == Some("VariableDeclaration"))
+        .filter(|n| string(n, "mutability") == Some("constant"))
+        .filter_map(|n| Some((string(n, "name")?, number(n.get("value")?)?)))
+        .collect::<HashMap<&str, U256>>();
+
+    let mut writes = vec![];
+    for contract in linearized {
+        let contract_name = string(contract, "name").unwrap_or_default();
+
+        for node in children(contract) {
+            if !matches!(
+                node_type(node),
+                Some("FunctionDefinition" | "ModifierDefinition")
+            ) {
+                continue;
+            }
+            let function = match string(node, "kind") {
+                Some("constructor") => "constructor",
+                _ => string(node, "name").unwrap_or_default(),
+            };
+            let Some(body) = node.get("body") else {
+                continue;
+            };
+
+            visit(body, &mut |n| {
+                let Some(slot) = written_slot(n, &constants) else {
+                    return;
+                };
+                for (label, reserved_slot) in reserved {
+                    if slot == U256::from_be_bytes(reserved_slot.0) {
+                        writes.push(format!(
+                            "{name}: `{contract_name}.{function}` writes the {label} \
+                             ({reserved_slot})"
+                        ));
+                    }
+                }
+            });
+        }
+    }
+
+    writes
+}
+
+/// The slot written by an assembly `sstore` or obtained from a `StorageSlot.get*Slot` getter, if
+/// it is a number literal or a constant.
+fn written_slot(node: &Value, constants: &HashMap<&str, U256>) -> Option<U256> {
+    let slot = match node_type(node)? {
+        "YulFunctionCall"
+            if node.get("functionName").and_then(|f| string(f, "name")) == Some("sstore") =>
+        {
+            node.get("arguments")?.get(0)?
+        }
+        "FunctionCall"
+            if node
+                .get("expression")
+                .and_then(|e| string(e, "memberName"))
+                .is_some_and(|getter| getter.starts_with("get") && getter.ends_with("Slot")) =>
+        {
+            unwrap_conversions(node.get("arguments")?.get(0)?)
+        }
+        _ => return None,
+    };
+
+    match node_type(slot)? {
+        "Identifier" | "YulIdentifier" => constants.get(string(slot, "name")?).copied(),
+        _ => number(slot),
+    }
+}
+
+/// The value of a number literal, in Solidity or in inline assembly.
+fn number(node: &Value) -> Option<U256> {
+    if !matches!(node_type(node)?, "Literal" | "YulLiteral") || string(node, "kind")? != "number" {
+        return None;
+    }
+
+    let value = string(node, "value")?.replace('_', "");
+    match value.strip_prefix("0x") {
+        Some(hex) => U256::from_str_radix(hex, 16).ok(),
+        None => U256::from_str_radix(&value, 10).ok(),
+    }
+}
+
+/// The functions and modifiers declared by the contracts by id, with their contract's name.
+fn definitions<'a>(contracts: &[&'a Value]) -> HashMap<i64, (&'a str, &'a Value)> {
+    contracts
+        .iter()
+        .flat_map(|c| {
+            let contract_name = string(c, "name").unwrap_or_default();
+            children(c)
+                .filter(|n| {
+                    matches!(
+                        node_type(n),
+                        Some("FunctionDefinition" | "ModifierDefinition")
+                    )
+                })
+                .filter_map(move |n| Some((n.get("id")?.as_i64()?, (contract_name, n))))
+        })
+        .collect()
+}
+
+/// The state variables declared by the contracts by id, with their name and mutability.
+fn state_variables<'a>(contracts: &[&'a Value]) -> HashMap<i64, (&'a str, &'a str)> {
+    contracts
+        .iter()
+        .flat_map(|c| children(c))
+        .filter(|n| node_type(n) == Some("VariableDeclaration"))
+        .filter_map(|n| {
+            let id = n.get("id")?.as_i64()?;
+            Some((id, (string(n, "name")?, string(n, "mutability")?)))
+        })
+        .collect()
+}
+
+fn is_selfdestruct(node: &Value) -> bool {
+    match node_type(node) {
+        Some("Identifier") => string(node, "name") == Some("selfdestruct"),
+        Some("YulFunctionCall") => {
+            node.get("functionName").and_then(|f| string(f, "name")) == Some("selfdestruct")
+        }
+        _ => false,
+    }
+}
+
+fn is_untrusted_delegatecall(node: &Value, variables: &HashMap<i64, (&str, &str)>) -> bool {
+    match node_type(node) {
+        Some("MemberAccess") if string(node, "memberName") == Some("delegatecall") => {
+            let trusted = node
+                .get("expression")
+                .map(unwrap_conversions)
+                .and_then(referenced_declaration)
+                .and_then(|id| variables.get(&id))
+                .is_some_and(|(_, mutability)| matches!(*mutability, "constant" | "immutable"));
+            !trusted
+        }
+        // Inline assembly targets cannot be resolved.
+        Some("YulFunctionCall") => {
+            node.get("functionName").and_then(|f| string(f, "name")) == Some("delegatecall")
+        }
+        _ => false,
+    }
+}
+
+/// The name of the mutable state variable assigned, incremented or deleted by the node.
+fn written_state_variable<'a>(
+    node: &Value,
+    mutable: &HashSet<i64>,
+    variables: &HashMap<i64, (&'a str, &str)>,
+) -> Option<&'a str> {
+    let target = match node_type(node)? {
+        "Assignment" => node.get("leftHandSide")?,
+        "UnaryOperation" if matches!(string(node, "operator")?, "++" | "--" | "delete") => {
+            node.get("subExpression")?
+        }
+        _ => return None,
+    };
+
+    let id = referenced_declaration(storage_root(target))?;
+    mutable.contains(&id).then(|| variables[&id].0)
+}
+
+/// The base of an indexed or member access, e.g. `balances` in `balances[a].amount`.
+fn storage_root(mut node: &Value) -> &Value {
+    loop {
+        let next = match node_type(node) {
+            Some("IndexAccess") => node.get("baseExpression"),
+            Some("MemberAccess") => node.get("expression"),
+            _ => None,
+        };
+        match next {
+            Some(next) => node = next,
+            None => return node,
+        }
+    }
+}
+
+/// The argument of type conversions, e.g. `target` in `address(target)`.
+fn unwrap_conversions(mut node: &Value) -> &Value {
+    while node_type(node) == Some("FunctionCall") && string(node, "kind") == Some("typeConversion")
+    {
+        match node.get("arguments").and_then(|a| a.get(0)) {
+            Some(argument) => node = argument,
+            None => break,
+        }
+    }
+    node
+}
+
+fn referenced_declaration(node: &Value) -> Option<i64> {
+    (node_type(node) == Some("Identifier"))
+        .then(|| node.get("referencedDeclaration")?.as_i64())
+        .flatten()
+}
+
+fn children(node: &Value) -> impl Iterator<Item = &Value> {
+    node.get("nodes")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+}
+
+fn node_type(node: &Value) -> Option<&str> {
+    string(node, "nodeType")
+}
+
+fn string<'a>(node: &'a Value, key: &str) -> Option<&'a str> {
+    node.get(key).and_then(Value::as_str)
+}
+
+/// Call `f` on every AST node under `value`, itself included.
+fn visit<'a>(value: &'a Value, f: &mut impl FnMut(&'a Value)) {
+    match value {
+        Value::Object(map) => {
+            if map.contains_key("nodeType") {
+                f(value);
+            }
+            map.values().for_each(|v| visit(v, f));
+        }
+        Value::Array(values) => values.iter().for_each(|v| visit(v, f)),
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn contract(name: &str, nodes: Vec<Value>) -> Value {
+        json!({ "nodeType": "ContractDefinition", "name": name, "nodes": nodes })
+    }
+
+    fn variable(id: i64, name: &str, mutability: &str, initialized: bool) -> Value {
+        let value = initialized.then(|| json!({ "nodeType": "Literal", "value": "1" }));
+        json!({
+            "nodeType": "VariableDeclaration",
+            "id": id,
+            "name": name,
+            "mutability": mutability,
+            "value": value,
+        })
+    }
+
+    fn function(kind: &str, name: &str, statements: Vec<Value>) -> Value {
+        json!({
+            "nodeType": "FunctionDefinition",
+            "kind": kind,
+            "name": name,
+            "body": { "nodeType": "Block", "statements": statements },
+        })
+    }
+
+    fn identifier(id: i64) -> Value {
+        json!({ "nodeType": "Identifier", "referencedDeclaration": id })
+    }
+
+    fn assignment(target: Value) -> Value {
+        json!({
+            "nodeType": "ExpressionStatement",
+            "expression": {
+                "nodeType": "Assignment",
+                "operator": "=",
+                "leftHandSide": target,
+                "rightHandSide": { "nodeType": "Literal", "value": "1" },
+            },
+        })
+    }
+
+    fn delegatecall(target: Value) -> Value {
+        json!({
+            "nodeType": "FunctionCall",
+            "expression": {
+                "nodeType": "MemberAccess",
+                "memberName": "delegatecall",
+                "expression": target,
+            },
+        })
+    }
+
+    fn lint(linearized: &[Value], options: &LintOptions) -> Vec<(Rule, String)> {
+        lint_contract(
+            "Module",
+            &linearized.iter().collect::<Vec<&Value>>(),
+            options,
+        )
+        .into_iter()
+        .map(|f| (f.rule, f.message))
+        .collect()
+    }
+
+    fn warn_all() -> LintOptions {
+        LintOptions {
+            constructor_storage_write: Severity::Warn,
+            selfdestruct: Severity::Warn,
+            state_initializer: Severity::Warn,
+            untrusted_delegatecall: Severity::Warn,
+        }
+    }
+
+    #[test]
+    fn reports_state_initializers() {
+        let module = contract(
+            "Module",
+            vec![
+                variable(1, "count", "mutable", true),
+                variable(2, "LIMIT", "constant", true),
+                variable(3, "owner", "mutable", false),
+            ],
+        );
+
+        assert_eq!(
+            lint(&[module], &warn_all()),
+            [(
+                Rule::StateInitializer,
+                "`Module.count` is initialized at declaration".to_string()
+            )]
+        );
+    }
+
+    #[test]
+    fn reports_selfdestruct_in_solidity_and_assembly() {
+        let module = contract(
+            "Module",
+            vec![
+                function(
+                    "function",
+                    "kill",
+                    vec![json!({ "nodeType": "Identifier", "name": "selfdestruct" })],
+                ),
+                function(
+                    "function",
+                    "burn",
+                    vec![json!({
+                        "nodeType": "InlineAssembly",
+                        "AST": {
+                            "nodeType": "YulFunctionCall",
+                            "functionName": { "nodeType": "YulIdentifier", "name": "selfdestruct" },
+                        },
+                    })],
+                ),
+            ],
+        );
+
+        assert_eq!(
+            lint(&[module], &warn_all()),
+            [
+                (
+                    Rule::Selfdestruct,
+                    "`Module.kill` calls `selfdestruct`".to_string()
+                ),
+                (
+                    Rule::Selfdestruct,
+                    "`Module.burn` calls `selfdestruct`".to_string()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn reports_delegatecalls_to_mutable_targets() {
+        let module = contract(
+            "Module",
+            vec![
+                variable(1, "target", "mutable", false),
+                variable(2, "IMPLEMENTATION", "immutable", false),
+                function("function", "forward", vec![delegatecall(identifier(1))]),
+                function(
+                    "function",
+                    "trusted",
+                    vec![delegatecall(json!({
+                        "nodeType": "FunctionCall",
+                        "kind": "typeConversion",
+                        "arguments": [identifier(2)],
+                    }))],
+                ),
+            ],
+        );
+
+        let findings = lint(&[module], &warn_all());
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].0, Rule::UntrustedDelegatecall);
+        assert!(findings[0].1.starts_with("`Module.forward` delegatecalls"));
+    }
+
+    #[test]
+    fn reports_constructor_writes_to_mutable_state() {
+        let module = contract(
+            "Module",
+            vec![
+                variable(1, "balances", "mutable", false),
+                variable(2, "owner", "immutable", false),
+                function(
+                    "constructor",
+                    "",
+                    vec![
+                        assignment(json!({
+                            "nodeType": "IndexAccess",
+                            "baseExpression": identifier(1),
+                            "indexExpression": { "nodeType": "Literal", "value": "0" },
+                        })),
+                        assignment(identifier(2)),
+                    ],
+                ),
+                function("function", "deposit", vec![assignment(identifier(1))]),
+            ],
+        );
+
+        assert_eq!(
+            lint(&[module], &warn_all()),
+            [(
+                Rule::ConstructorStorageWrite,
+                "`Module.constructor` writes `balances`".to_string()
+            )]
+        );
+    }
+
+    #[test]
+    fn follows_constructor_calls_and_modifiers() {
+        let call = |id: i64| {
+            json!({
+                "nodeType": "ExpressionStatement",
+                "expression": { "nodeType": "FunctionCall", "expression": identifier(id) },
+            })
+        };
+        let mut constructor = function("constructor", "", vec![call(11)]);
+        constructor["modifiers"] = json!([{
+            "nodeType": "ModifierInvocation",
+            "modifierName": { "nodeType": "IdentifierPath", "referencedDeclaration": 12 },
+        }]);
+        let mut init = function(
+            "function",
+            "_init",
+            vec![assignment(identifier(1)), call(11)],
+        );
+        init["id"] = json!(11);
+        let mut setup = function(
+            "modifier",
+            "setup",
+            vec![json!({
+                "nodeType": "InlineAssembly",
+                "AST": {
+                    "nodeType": "YulFunctionCall",
+                    "functionName": { "nodeType": "YulIdentifier", "name": "sstore" },
+                },
+            })],
+        );
+        setup["nodeType"] = json!("ModifierDefinition");
+        setup["id"] = json!(12);
+
+        let module = contract("Module", vec![constructor]);
+        let base = contract(
+            "Base",
+            vec![variable(1, "owner", "mutable", false), init, setup],
+        );
+
+        assert_eq!(
+            lint(&[module, base], &warn_all()),
+            [
+                (
+                    Rule::ConstructorStorageWrite,
+                    "`Module.constructor` writes storage with `sstore` through `Base.setup`"
+                        .to_string()
+                ),
+                (
+                    Rule::ConstructorStorageWrite,
+                    "`Module.constructor` writes `owner` through `Base._init`".to_string()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn checks_inherited_contracts() {
+        let module = contract("Module", vec![]);
+        let base = contract("Base", vec![variable(1, "count", "mutable", true)]);
+
+        assert_eq!(
+            lint(&[module, base], &warn_all()),
+            [(
+                Rule::StateInitializer,
+                "`Base.count` is initialized at declaration".to_string()
+            )]
+        );
+    }
+
+    #[test]
+    fn skips_allowed_rules_and_fails_on_denied_ones() {
+        let module = contract(
+            "Module",
+            vec![
+                variable(1, "count", "mutable", true),
+                function(
+                    "function",
+                    "kill",
+                    vec![json!({ "nodeType": "Identifier", "name": "selfdestruct" })],
+                ),
+            ],
+        );
+        let options = LintOptions {
+            state_initializer: Severity::Allow,
+            ..warn_all()
+        };
+        assert_eq!(lint(&[module.clone()], &options).len(), 1);
+
+        let findings = |options: &LintOptions| lint_contract("Module", &[&module], options);
+        let warnings = check_findings(findings(&warn_all())).unwrap();
+        assert_eq!(warnings.len(), 2);
+        assert!(warnings[0].starts_with("Module [state_initializer]: "));
+
+        let options = LintOptions {
+            selfdestruct: Severity::Deny,
+            ..warn_all()
+        };
+        assert!(check_findings(findings(&options)).is_err());
+    }
+
+    #[test]
+    fn finds_writes_to_reserved_slots() {
+        let slot = B256::repeat_byte(0x36);
+        let literal =
+            |kind: &str| json!({ "nodeType": kind, "kind": "number", "value": slot.to_string() });
+        let module = contract(
+            "Module",
+            vec![
+                json!({
+                    "nodeType": "VariableDeclaration",
+                    "name": "IMPLEMENTATION_SLOT",
+                    "mutability": "constant",
+                    "value": literal("Literal"),
+                }),
+                function(
+                    "function",
+                    "upgrade",
+                    vec![json!({
+                        "nodeType": "InlineAssembly",
+                        "AST": {
+                            "nodeType": "YulFunctionCall",
+                            "functionName": { "nodeType": "YulIdentifier", "name": "sstore" },
+                            "arguments": [
+                                { "nodeType": "YulIdentifier", "name": "IMPLEMENTATION_SLOT" },
+                                { "nodeType": "YulIdentifier", "name": "implementation" },
+                            ],
+                        },
+                    })],
+                ),
+                function(
+                    "constructor",
+                    "",
+                    vec![json!({
+                        "nodeType": "FunctionCall",
+                        "expression": {
+                            "nodeType": "MemberAccess",
+                            "memberName": "getAddressSlot",
+                            "expression": { "nodeType": "Identifier", "name": "StorageSlot" },
+                        },
+                        "arguments": [literal("Literal")],
+                    })],
+                ),
+                function(
+                    "function",
+                    "read",
+                    vec![json!({
+                        "nodeType": "YulFunctionCall",
+                        "functionName": { "nodeType": "YulIdentifier", "name": "sload" },
+                        "arguments": [literal("YulLiteral")],
+                    })],
+                ),
+            ],
+        );
+
+        let writes = slot_writes("Module", &[&module], &[("proxy slot", slot)]);
+        assert_eq!(
+            writes,
+            [
+                format!("Module: `Module.upgrade` writes the proxy slot ({slot})"),
+                format!("Module: `Module.constructor` writes the proxy slot ({slot})"),
+            ]
+        );
+        assert!(slot_writes("Module", &[&module], &[("other", B256::ZERO)]).is_empty());
+    }
+}
@@ -1,69 +1,248 @@
 use std::{
-    collections::{BTreeMap, HashMap},
+    collections::{BTreeMap, BTreeSet, HashMap},
+    fmt,
     path::{Path, PathBuf},
 };
 
-use alloy_json_abi::{Function, JsonAbi};
+use alloy_json_abi::{Function, JsonAbi, StateMutability};
 use alloy_primitives::{hex::ToHexExt, keccak256, Address, Selector, B256};
 use eyre::{eyre, Result};
+use features::{render_feature_admin, render_feature_calls, render_feature_functions, Feature};
 use foundry_compilers::{
-    artifacts::ConfigurableContractArtifact, info::ContractInfo, Project, ProjectCompileOutput,
+    artifacts::ConfigurableContractArtifact, compilers::multi::MultiCompilerParsedSource,
+    info::ContractInfo, Graph, Project, ProjectCompileOutput,
 };
+use guards::{render_guard_calls, render_guard_functions, Access, Guard};
+use hooks::{render_hooks, render_imports, validate_hooks, Hook};
 use itertools::Itertools;
-use utils::{repeat_string, to_constant_case};
+use serde::Deserialize;
+use utils::repeat_string;
 
 pub mod deterministric;
+pub mod diff;
+pub mod facade;
+pub mod features;
+pub mod guards;
+pub mod hooks;
 pub mod immutable;
+pub mod inspect;
+pub mod lint;
+pub mod naming;
+pub mod output;
+pub mod patterns;
+pub mod pipeline;
+pub mod proxy;
+pub mod registry;
+pub mod scaffold;
+pub mod scope;
+pub mod script;
+pub mod storage;
 pub mod toml;
+pub mod unknown;
+pub mod upgradeable;
+pub mod verify;
 
 mod utils;
 
 #[derive(Debug, Clone)]
 pub struct Module {
     pub contract_identifier: String,
+    /// `<path>:<Name>` identifier of the module's contract.
+    pub contract: String,
+    /// Name of the module in generated code, its alias or its contract name.
     pub contract_name: String,
+    /// Unique constant naming the module in generated code.
+    pub constant_name: String,
+    /// Unique struct field and constructor parameter naming the module in generated code.
+    pub variable_name: String,
     pub function_name: String,
+    pub signature: String,
     pub selector: Selector,
+    pub state_mutability: StateMutability,
     pub address: Option<Address>,
 }
 
-/// Identity the source files for the given module names without compiling.
-pub fn identify_sources(project: &Project, module_names: Vec<String>) -> Result<Vec<PathBuf>> {
+/// Checks the router performs before dispatching, shared by every flavor.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct DispatchOptions {
+    /// Library functions called before dispatching.
+    #[serde(default)]
+    pub hooks: Vec<Hook>,
+    /// Access control enforced on selected modules and selectors.
+    #[serde(default)]
+    pub guards: Vec<Guard>,
+    /// Named groups of selectors that can be toggled on-chain.
+    #[serde(default)]
+    pub features: Vec<Feature>,
+    /// Who may toggle features.
+    pub feature_admin: Option<Access>,
+    /// Directory the router is written to, hook imports are made relative to it.
+    /// Imports stay relative to the project root when unset.
+    #[serde(skip)]
+    pub output_dir: Option<PathBuf>,
+}
+
+impl DispatchOptions {
+    pub fn is_empty(&self) -> bool {
+        self.hooks.is_empty() && self.guards.is_empty() && self.features.is_empty()
+    }
+
+    /// The same options for a router written to `path`.
+    pub fn with_output(&self, path: &Path) -> Self {
+        Self {
+            output_dir: path.parent().map(Path::to_path_buf),
+            ..self.clone()
+        }
+    }
+}
+
+/// A module of a router, optionally aliased.
+///
+/// The alias replaces the contract name in generated identifiers, so that contracts sharing a
+/// name can be routed together. In TOML an entry is either a contract identifier or a table such
+/// as `{ contract = "lib/x/src/Owner.sol:OwnerModule", alias = "ExternalOwner" }`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Deserialize)]
+#[serde(from = "ModuleEntryDefinition")]
+pub struct ModuleEntry {
+    /// `<path>:<Name>` identifier of the contract.
+    pub contract: String,
+    pub alias: Option<String>,
+}
+
+impl ModuleEntry {
+    pub fn new(contract: impl Into<String>) -> Self {
+        Self {
+            contract: contract.into(),
+            alias: None,
+        }
+    }
+
+    pub fn info(&self) -> ContractInfo {
+        ContractInfo::new(&self.contract)
+    }
+
+    /// The name of the module in generated code, its alias or its contract name.
+    pub fn name(&self) -> String {
+        self.alias.clone().unwrap_or_else(|| self.info().name)
+    }
+}
+
+impl From<String> for ModuleEntry {
+    fn from(contract: String) -> Self {
+        Self::new(contract)
+    }
+}
+
+impl fmt::Display for ModuleEntry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.alias {
+            Some(alias) => write!(f, "{} (alias {alias})", self.contract),
+            None => f.write_str(&self.contract),
+        }
+    }
+}
+
+/// A module list entry as written in TOML.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum ModuleEntryDefinition {
+    Contract(String),
+    Aliased { contract: String, alias: String },
+}
+
+impl From<ModuleEntryDefinition> for ModuleEntry {
+    fn from(definition: ModuleEntryDefinition) -> Self {
+        match definition {
+            ModuleEntryDefinition::Contract(contract) => Self::new(contract),
+            ModuleEntryDefinition::Aliased { contract, alias } => Self {
+                contract,
+                alias: Some(alias),
+            },
+        }
+    }
+}
+
+/// The contract identifiers of the given modules.
+pub fn module_contracts(modules: &[ModuleEntry]) -> Vec<String> {
+    modules.iter().map(|m| m.contract.clone()).collect()
+}
+
+/// Identity the source files for the given contract identifiers without compiling.
+pub fn identify_sources(project: &Project, contracts: Vec<String>) -> Result<Vec<PathBuf>> {
     let mut sources = vec![];
-    for module in module_names {
-        let identifer = ContractInfo::new(module.as_str());
+    for contract in contracts {
+        let identifer = ContractInfo::new(&contract);
         let path = project.paths.resolve_import(
             project.root(),
-            Path::new(&identifer.path.unwrap_or_else(|| module.clone())),
+            Path::new(&identifer.path.unwrap_or_else(|| contract.clone())),
         )?;
         sources.push(path);
     }
     Ok(sources)
 }
 
-/// Collect the sources for the given module names from the project compile output.
-pub fn collect_sources(
+/// Identify the source files of the given contract identifiers and every file they import.
+pub fn router_sources(project: &Project, contracts: Vec<String>) -> Result<BTreeSet<PathBuf>> {
+    let sources = identify_sources(project, contracts)?;
+    let graph = Graph::<MultiCompilerParsedSource>::resolve(&project.paths)?;
+
+    let mut files = BTreeSet::new();
+    for source in sources {
+        files.extend(graph.imports(&source).into_iter().map(|p| p.to_path_buf()));
+        files.insert(source);
+    }
+    Ok(files)
+}
+
+/// Find the compiled artifacts for the given modules, sorted by module name.
+///
+/// Aliased modules are named after their alias.
+pub fn find_artifacts(
     project: &Project,
     output: &ProjectCompileOutput,
-    module_names: Vec<String>,
-    deployer: Option<Address>,
-    salt: Option<B256>,
-) -> Result<(HashMap<Selector, Module>, JsonAbi)> {
-    let mut targets: HashMap<String, Option<(ContractInfo, ConfigurableContractArtifact)>> =
-        module_names.into_iter().map(|name| (name, None)).collect();
+    modules: Vec<ModuleEntry>,
+) -> Result<Vec<(String, ConfigurableContractArtifact)>> {
+    Ok(find_module_artifacts(project, output, modules)?
+        .into_iter()
+        .map(|(module, artifact)| (module.name(), artifact))
+        .collect())
+}
+
+/// Find the compiled artifacts for the given modules along with their entries, sorted by module
+/// name.
+pub fn find_module_artifacts(
+    project: &Project,
+    output: &ProjectCompileOutput,
+    modules: Vec<ModuleEntry>,
+) -> Result<Vec<(ModuleEntry, ConfigurableContractArtifact)>> {
+    for module in &modules {
+        if let Some(alias) = &module.alias {
+            let valid = alias.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+                && alias.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+            if !valid {
+                return Err(eyre!(
+                    "Invalid alias `{alias}` for module `{}`",
+                    module.contract
+                ));
+            }
+        }
+    }
+
+    let mut targets: HashMap<ModuleEntry, Option<ConfigurableContractArtifact>> =
+        modules.into_iter().map(|module| (module, None)).collect();
 
     let mut remaining_modules = targets.len();
 
     for (path, name, info) in output.clone().into_artifacts_with_files() {
-        for (module_name, module_info) in &mut targets {
+        for (module, module_info) in &mut targets {
             if module_info.is_some() {
                 continue; // Skip already matched modules
             }
 
-            let target = ContractInfo::new(module_name);
+            let target = module.info();
             if let Some(target_path) = &target.path {
                 if is_matching_path(project, &path, target_path) && target.name == name {
-                    *module_info = Some((target, info.clone()));
+                    *module_info = Some(info.clone());
                     remaining_modules -= 1;
                     break;
                 }
@@ -78,24 +257,49 @@ pub fn collect_sources(
     if remaining_modules > 0 {
         let missing_modules: Vec<String> = targets
             .into_iter()
-            .filter_map(|(name, info)| if info.is_none() { Some(name) } else { None })
+            .filter_map(|(module, info)| {
+                if info.is_none() {
+                    Some(module.contract)
+                } else {
+                    None
+                }
+            })
             .collect();
         return Err(eyre!("Modules not found: {}", missing_modules.join(", ")));
     }
 
-    let sources: Vec<(String, ConfigurableContractArtifact)> = targets
+    let sources: Vec<(ModuleEntry, ConfigurableContractArtifact)> = targets
         .into_iter()
-        .map(|(_, info)| info.unwrap())
-        .map(|(info, artifact)| (info.name, artifact))
-        .sorted_by(|(a, _), (b, _)| a.cmp(b))
+        .map(|(module, info)| (module, info.unwrap()))
+        .sorted_by_cached_key(|(module, _)| (module.name(), module.contract.clone()))
         .collect();
 
+    Ok(sources)
+}
+
+/// Collect the sources for the given modules from the project compile output.
+pub fn collect_sources(
+    project: &Project,
+    output: &ProjectCompileOutput,
+    module_names: Vec<ModuleEntry>,
+    deployer: Option<Address>,
+    salt: Option<B256>,
+    router_identifiers: &[&str],
+) -> Result<(HashMap<Selector, Module>, JsonAbi)> {
+    let sources = find_module_artifacts(project, output, module_names)?;
+
     let mut combined_abi = JsonAbi::new();
     let mut functions = BTreeMap::<Selector, Function>::new();
     let mut selectors = HashMap::new();
 
-    for module in sources.iter() {
-        let (module_name, artifact) = module;
+    let contract_names = sources
+        .iter()
+        .map(|(module, _)| module.name())
+        .collect::<Vec<_>>();
+    let (names, _) = naming::module_names(&contract_names, router_identifiers);
+
+    for ((module, artifact), names) in sources.iter().zip(names) {
+        let module_name = module.name();
 
         let address = if deployer.is_some() && salt.is_some() {
             let bytecode = artifact
@@ -137,15 +341,19 @@ pub fn collect_sources(
                         .insert(function.name.clone(), vec![function.clone()]);
                 };
 
-                let identifier =
-                    keccak256(to_constant_case(module_name.as_str())).encode_hex_with_prefix();
+                let identifier = keccak256(&names.identifier).encode_hex_with_prefix();
                 selectors.insert(
                     function.selector(),
                     Module {
                         contract_identifier: identifier,
+                        contract: module.contract.clone(),
                         contract_name: module_name.clone(),
+                        constant_name: names.constant.clone(),
+                        variable_name: names.variable.clone(),
                         function_name: function.name.clone(),
+                        signature: function.signature(),
                         selector: function.selector(),
+                        state_mutability: function.state_mutability,
                         address,
                     },
                 );
@@ -181,9 +389,11 @@ fn generate_router<F, K>(
     project: &Project,
     output: &ProjectCompileOutput,
     router_name: String,
-    module_names: Vec<String>,
+    module_names: Vec<ModuleEntry>,
     deployer: Option<Address>,
     salt: Option<B256>,
+    router_identifiers: &[&str],
+    dispatch: &DispatchOptions,
     selector_template: K,
     router_template: F,
 ) -> Result<String>
@@ -191,8 +401,19 @@ where
     F: Fn(&String, &Vec<Module>, &JsonAbi) -> Result<String>,
     K: Fn(&Module) -> String,
 {
-    let (selectors, abi) =
-        collect_sources(&project, &output, module_names.clone(), deployer, salt)?;
+    let (selectors, abi) = collect_sources(
+        &project,
+        &output,
+        module_names.clone(),
+        deployer,
+        salt,
+        router_identifiers,
+    )?;
+
+    validate_hooks(project, output, &dispatch.hooks)?;
+
+    let abi = guards::extend_abi(&dispatch.guards, &abi)?;
+    let abi = features::extend_abi(&dispatch.features, &abi)?;
 
     let leafs = selectors
         .iter()
@@ -214,11 +435,19 @@ where
         &selectors,
         &modules,
         &abi,
+        dispatch,
         selector_template,
         router_template,
     )?;
 
-    Ok(router_content)
+    // Hook imports depend on where the router is written.
+    let imports = render_imports(
+        &dispatch.hooks,
+        project.root(),
+        dispatch.output_dir.as_deref(),
+    )?;
+
+    Ok(router_content.replace("{imports}\n", &imports))
 }
 
 fn render_router<F, K>(
@@ -227,6 +456,7 @@ fn render_router<F, K>(
     selectors: &HashMap<Selector, Module>,
     modules: &Vec<Module>,
     abi: &JsonAbi,
+    dispatch: &DispatchOptions,
     render_selector: K,
     render_template: F,
 ) -> Result<String>
@@ -234,11 +464,24 @@ where
     F: Fn(&String, &Vec<Module>, &JsonAbi) -> Result<String>,
     K: Fn(&Module) -> String,
 {
-    let tree = render_tree(root, selectors, &render_selector);
+    // Feature flags are checked before guards in each affected `case`.
+    let mut case_checks = render_feature_calls(&dispatch.features, selectors)?;
+    for (selector, calls) in render_guard_calls(&dispatch.guards, selectors)? {
+        case_checks.entry(selector).or_default().push_str(&calls);
+    }
+    let tree = render_tree(root, selectors, &case_checks, &render_selector);
+
+    let checks =
+        render_feature_functions(&dispatch.features)? + &render_guard_functions(&dispatch.guards)?;
+    let feature_admin = render_feature_admin(&dispatch.features, dispatch.feature_admin.as_ref())?;
 
     let router_content = render_template(&router_name, &modules, &abi)?;
 
-    let router_content = router_content.replace("{selectors}", &tree);
+    let router_content = router_content
+        .replace("{hooks}\n", &render_hooks(&dispatch.hooks, selectors)?)
+        .replace("{checks}\n", &checks)
+        .replace("{feature_admin}\n", &feature_admin)
+        .replace("{selectors}", &tree);
 
     Ok(router_content)
 }
@@ -287,9 +530,26 @@ fn build_binary_tree(selectors: Vec<Selector>) -> TreeNode {
     root
 }
 
+/// The number of binary splits on the dispatch path of each selector.
+fn tree_depths(root: &TreeNode) -> HashMap<Selector, usize> {
+    fn visit(node: &TreeNode, depth: usize, depths: &mut HashMap<Selector, usize>) {
+        for selector in &node.selectors {
+            depths.insert(*selector, depth);
+        }
+        for child in &node.children {
+            visit(child, depth + 1, depths);
+        }
+    }
+
+    let mut depths = HashMap::new();
+    visit(root, 0, &mut depths);
+    depths
+}
+
 fn render_tree<F>(
     root: &TreeNode,
     selectors: &HashMap<Selector, Module>,
+    case_checks: &HashMap<Selector, String>,
     render_selector: F,
 ) -> String
 where
@@ -303,6 +563,7 @@ where
         indent: usize,
         selectors_str: &mut Vec<String>,
         selectors: &HashMap<Selector, Module>,
+        case_checks: &HashMap<Selector, String>,
         render_selector: &F,
     ) where
         F: Fn(&Module) -> String,
@@ -331,6 +592,7 @@ where
                 indent + 1,
                 selectors_str,
                 selectors,
+                case_checks,
                 render_selector,
             );
             selectors_str.push(format!("{}}}", repeat_string("    ", indent)));
@@ -340,15 +602,21 @@ where
                 indent,
                 selectors_str,
                 selectors,
+                case_checks,
                 render_selector,
             );
         } else {
             selectors_str.push(format!("{}switch sig", repeat_string("    ", indent)));
             for selector in &node.selectors {
+                let module = selectors.get(selector).unwrap();
                 selectors_str.push(format!(
-                    "{}{}",
+                    "{}case {} {{ {}{} }} // {}.{}()",
                     repeat_string("    ", indent + 1),
-                    render_selector(selectors.get(selector).unwrap())
+                    selector.encode_hex_with_prefix(),
+                    case_checks.get(selector).map_or("", |calls| calls.as_str()),
+                    render_selector(module),
+                    module.contract_name,
+                    module.function_name
                 ));
             }
             selectors_str.push(format!("{}leave", repeat_string("    ", indent)));
@@ -360,6 +628,7 @@ where
         4,
         &mut selectors_str,
         selectors,
+        case_checks,
         &render_selector,
     );
 
@@ -377,3 +646,34 @@ where
         .collect::<Vec<String>>()
         .join("\n")
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The module of `contract`, a `<path>:<Name>` identifier, routing `signature`.
+    pub(crate) fn module(contract: &str, signature: &str) -> Module {
+        let function = Function::parse(signature).unwrap();
+        let contract_name = ContractInfo::new(contract).name;
+        let (names, _) = naming::module_names(&[contract_name.clone()], &[]);
+        let names = names.into_iter().next().unwrap();
+
+        Module {
+            contract_identifier: keccak256(&names.identifier).encode_hex_with_prefix(),
+            contract: contract.to_string(),
+            contract_name,
+            constant_name: names.constant,
+            variable_name: names.variable,
+            function_name: function.name.clone(),
+            signature: function.signature(),
+            selector: function.selector(),
+            state_mutability: function.state_mutability,
+            address: Some(Address::from_word(keccak256(contract))),
+        }
+    }
+
+    /// The modules by selector, as collected from the compiler output.
+    pub(crate) fn routed(modules: impl IntoIterator<Item = Module>) -> HashMap<Selector, Module> {
+        modules.into_iter().map(|m| (m.selector, m)).collect()
+    }
+}
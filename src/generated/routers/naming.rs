@@ -0,0 +1,413 @@
+use std::{collections::HashSet, fmt};
+
+use super::utils::{to_constant_case, to_lower_camel_case};
+
+/// Solidity keywords, reserved words and global names that cannot be declared.
+const RESERVED: &[&str] = &[
+    "abi",
+    "abstract",
+    "address",
+    "addmod",
+    "after",
+    "alias",
+    "anonymous",
+    "apply",
+    "assembly",
+    "assert",
+    "auto",
+    "block",
+    "blockhash",
+    "bool",
+    "break",
+    "byte",
+    "bytes",
+    "calldata",
+    "case",
+    "catch",
+    "constant",
+    "constructor",
+    "continue",
+    "contract",
+    "copyof",
+    "days",
+    "default",
+    "define",
+    "delete",
+    "do",
+    "ecrecover",
+    "else",
+    "emit",
+    "enum",
+    "error",
+    "ether",
+    "event",
+    "external",
+    "fallback",
+    "false",
+    "final",
+    "fixed",
+    "for",
+    "function",
+    "gasleft",
+    "global",
+    "gwei",
+    "hours",
+    "if",
+    "immutable",
+    "implements",
+    "import",
+    "in",
+    "indexed",
+    "inline",
+    "int",
+    "interface",
+    "internal",
+    "is",
+    "keccak256",
+    "let",
+    "library",
+    "macro",
+    "mapping",
+    "match",
+    "memory",
+    "minutes",
+    "modifier",
+    "msg",
+    "mulmod",
+    "mutable",
+    "new",
+    "now",
+    "null",
+    "of",
+    "override",
+    "partial",
+    "payable",
+    "pragma",
+    "private",
+    "promise",
+    "public",
+    "pure",
+    "receive",
+    "reference",
+    "relocatable",
+    "require",
+    "return",
+    "returns",
+    "revert",
+    "ripemd160",
+    "sealed",
+    "seconds",
+    "selfdestruct",
+    "sha256",
+    "sizeof",
+    "static",
+    "storage",
+    "string",
+    "struct",
+    "super",
+    "supports",
+    "switch",
+    "this",
+    "throw",
+    "transient",
+    "true",
+    "try",
+    "tx",
+    "type",
+    "typedef",
+    "typeof",
+    "ufixed",
+    "uint",
+    "unchecked",
+    "unicode",
+    "using",
+    "var",
+    "view",
+    "virtual",
+    "weeks",
+    "wei",
+    "while",
+    "years",
+];
+
+/// The generated identifiers of a module.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ModuleNames {
+    /// Constant holding the module address or registry key, e.g. `FOO_BAR`.
+    pub constant: String,
+    /// Struct field and constructor parameter, e.g. `fooBar`.
+    pub variable: String,
+    /// Hashed into the module identifier, the historical constant case name unless it is shared.
+    pub identifier: String,
+}
+
+/// An identifier that could not be derived from the contract name as is.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rename {
+    pub contract_name: String,
+    pub kind: &'static str,
+    pub from: String,
+    pub to: String,
+}
+
+impl fmt::Display for Rename {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Module `{}` {} `{}` renamed to `{}`",
+            self.contract_name, self.kind, self.from, self.to
+        )
+    }
+}
+
+/// Whether `name` cannot be declared in Solidity.
+pub fn is_reserved(name: &str) -> bool {
+    if RESERVED.contains(&name) {
+        return true;
+    }
+
+    // Sized elementary types, e.g. `uint8`, `bytes32` or `fixed128x18`.
+    ["uint", "int", "bytes", "ufixed", "fixed"]
+        .iter()
+        .filter_map(|prefix| name.strip_prefix(prefix))
+        .any(|size| !size.is_empty() && size.chars().all(|c| c.is_ascii_digit() || c == 'x'))
+}
+
+/// Make `name` a valid Solidity identifier: only ASCII alphanumerics, `_` and `$`, not starting
+/// with a digit and not reserved.
+fn sanitize(name: &str) -> String {
+    let mut identifier = name
+        .chars()
+        .map(
+            |c| match c.is_ascii_alphanumeric() || c == '_' || c == '$' {
+                true => c,
+                false => '_',
+            },
+        )
+        .collect::<String>();
+
+    if identifier.is_empty() || identifier.starts_with(|c: char| c.is_ascii_digit()) {
+        identifier.insert(0, '_');
+    }
+    if is_reserved(&identifier) {
+        identifier.push('_');
+    }
+    identifier
+}
+
+/// The first of `name`, `name{separator}2`, `name{separator}3`... not taken yet nor declared by
+/// the router.
+fn unique(
+    name: String,
+    separator: &str,
+    taken: &mut HashSet<String>,
+    router_identifiers: &[&str],
+) -> String {
+    let mut candidate = name.clone();
+    let mut i = 2;
+    while taken.contains(&candidate)
+        || is_reserved(&candidate)
+        || router_identifiers.contains(&candidate.as_str())
+    {
+        candidate = format!("{name}{separator}{i}");
+        i += 1;
+    }
+    taken.insert(candidate.clone());
+    candidate
+}
+
+/// Derive unique, valid and non-reserved identifiers for the modules, in the given order.
+///
+/// Constants use constant case without the leading underscore of capitalized names, constants and
+/// variables avoid the `router_identifiers` the router declares next to them. Identifiers changed
+/// to be valid or unique are reported as renames.
+pub fn module_names(
+    contract_names: &[String],
+    router_identifiers: &[&str],
+) -> (Vec<ModuleNames>, Vec<Rename>) {
+    let mut constants = HashSet::new();
+    let mut variables = HashSet::new();
+    let mut identifiers = HashSet::new();
+    let mut renames = vec![];
+
+    let names = contract_names
+        .iter()
+        .map(|contract_name| {
+            let mut rename = |kind: &'static str, from: String, to: String| {
+                if from != to {
+                    renames.push(Rename {
+                        contract_name: contract_name.clone(),
+                        kind,
+                        from,
+                        to: to.clone(),
+                    });
+                }
+                to
+            };
+
+            let cased = to_constant_case(contract_name);
+            let preferred = match cased.trim_start_matches('_') {
+                "" => cased.clone(),
+                trimmed => trimmed.to_string(),
+            };
+            let constant = unique(
+                sanitize(&preferred),
+                "_",
+                &mut constants,
+                router_identifiers,
+            );
+            let constant = rename("constant", preferred, constant);
+
+            let preferred = to_lower_camel_case(contract_name);
+            let variable = unique(sanitize(&preferred), "", &mut variables, router_identifiers);
+            let variable = rename("variable", preferred, variable);
+
+            // Identifiers are registry keys, keep them unless two modules would share one.
+            let identifier = match identifiers.contains(&cased) {
+                true => unique(constant.clone(), "_", &mut identifiers, &[]),
+                false => unique(cased, "_", &mut identifiers, &[]),
+            };
+
+            ModuleNames {
+                constant,
+                variable,
+                identifier,
+            }
+        })
+        .collect();
+
+    (names, renames)
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy_primitives::{Address, B256};
+
+    use super::super::pipeline::Flavor;
+    use super::*;
+
+    fn names(contract_names: &[&str]) -> (Vec<ModuleNames>, Vec<Rename>) {
+        let contract_names = contract_names
+            .iter()
+            .map(|name| name.to_string())
+            .collect::<Vec<String>>();
+        module_names(&contract_names, &[])
+    }
+
+    #[test]
+    fn reserved_words() {
+        for name in [
+            "address",
+            "this",
+            "uint",
+            "uint8",
+            "int256",
+            "bytes32",
+            "fixed128x18",
+        ] {
+            assert!(is_reserved(name), "{name}");
+        }
+        for name in [
+            "Address",
+            "Uint8",
+            "uint8a",
+            "bytesLib",
+            "fixedPoint",
+            "Counter",
+        ] {
+            assert!(!is_reserved(name), "{name}");
+        }
+    }
+
+    #[test]
+    fn similar_contract_names_stay_unique() {
+        let (names, renames) = names(&["FooBar", "fooBar", "Foo_Bar", "FOOBar"]);
+
+        let constants = names
+            .iter()
+            .map(|n| n.constant.as_str())
+            .collect::<Vec<_>>();
+        let variables = names
+            .iter()
+            .map(|n| n.variable.as_str())
+            .collect::<Vec<_>>();
+        let identifiers = names
+            .iter()
+            .map(|n| n.identifier.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(constants, ["FOO_BAR", "FOO_BAR_2", "FOO__BAR", "FOOBAR"]);
+        assert_eq!(variables, ["fooBar", "fooBar2", "foo_Bar", "FOOBar"]);
+        assert_eq!(identifiers, ["_FOO_BAR", "FOO_BAR", "_FOO__BAR", "_FOOBAR"]);
+
+        assert_eq!(renames.len(), 2);
+        assert_eq!(
+            renames[0].to_string(),
+            "Module `fooBar` constant `FOO_BAR` renamed to `FOO_BAR_2`"
+        );
+        assert_eq!(
+            renames[1].to_string(),
+            "Module `fooBar` variable `fooBar` renamed to `fooBar2`"
+        );
+    }
+
+    #[test]
+    fn shared_identifiers_fall_back_to_the_constant() {
+        let (names, _) = names(&["Counter", "Counter"]);
+
+        assert_eq!(names[0].identifier, "_COUNTER");
+        assert_eq!(names[1].constant, "COUNTER_2");
+        assert_eq!(names[1].identifier, "COUNTER_2");
+    }
+
+    #[test]
+    fn invalid_and_reserved_names_are_renamed() {
+        let (names, renames) = names(&["uint8", "1inch", "this"]);
+
+        assert_eq!(names[0].variable, "uint8_");
+        assert_eq!(names[1].constant, "_1INCH");
+        assert_eq!(names[1].variable, "_1inch");
+        assert_eq!(names[2].variable, "this_");
+        assert_eq!(renames.len(), 4);
+    }
+
+    #[test]
+    fn router_identifiers_are_avoided_per_flavor() {
+        let (deployer, salt) = (Address::ZERO, B256::ZERO);
+        for (flavor, contract_name, constant) in [
+            (
+                Flavor::Deterministic { deployer, salt },
+                "FeaturesSlot",
+                "FEATURES_SLOT_2",
+            ),
+            (
+                Flavor::DeterministicYul { deployer, salt },
+                "FeaturesSlot",
+                "FEATURES_SLOT",
+            ),
+            (Flavor::Immutable, "FeaturesSlot", "FEATURES_SLOT_2"),
+            (
+                Flavor::Upgradeable { deployer, salt },
+                "ImplementationSlot",
+                "IMPLEMENTATION_SLOT_2",
+            ),
+            (
+                Flavor::Upgradeable { deployer, salt },
+                "AdminSlot",
+                "ADMIN_SLOT_2",
+            ),
+            (Flavor::Registry, "Registry", "REGISTRY_2"),
+            (Flavor::Registry, "CacheNamespace", "CACHE_NAMESPACE_2"),
+            (Flavor::Facade, "FeaturesSlot", "FEATURES_SLOT_2"),
+            (
+                Flavor::Deterministic { deployer, salt },
+                "Registry",
+                "REGISTRY",
+            ),
+        ] {
+            let (names, renames) =
+                module_names(&[contract_name.to_string()], flavor.router_identifiers());
+            assert_eq!(names[0].constant, constant, "{flavor:?}");
+            assert_eq!(renames.len(), usize::from(constant.ends_with("_2")));
+        }
+    }
+}
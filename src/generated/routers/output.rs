@@ -0,0 +1,72 @@
+use std::path::{Component, Path, PathBuf};
+
+use foundry_compilers::Project;
+
+/// Resolve where a generated router file is written.
+///
+/// `output` is a directory or a full file path relative to the project root, in which `{name}`
+/// is replaced by the router name. Without one, routers go to `<src>/generated/routers`. A file
+/// path keeps its stem and takes the given extension, so one template serves `.sol` and `.yul`.
+pub fn output_path(
+    project: &Project,
+    output: Option<&str>,
+    router_name: &str,
+    extension: &str,
+) -> PathBuf {
+    let file_name = format!("{router_name}.g.{extension}");
+
+    let Some(output) = output else {
+        return project
+            .sources_path()
+            .join("generated/routers")
+            .join(file_name);
+    };
+
+    let path = project.root().join(output.replace("{name}", router_name));
+    match path.extension() {
+        Some(_) => path.with_extension(extension),
+        None => path.join(file_name),
+    }
+}
+
+/// The import path of `target` from a file in `from_dir`, always starting with `./` or `../`.
+pub fn relative_import(from_dir: &Path, target: &Path) -> String {
+    let from = normalize(from_dir);
+    let target = normalize(target);
+
+    let common = from
+        .components()
+        .zip(target.components())
+        .take_while(|(a, b)| a == b)
+        .count();
+
+    let mut relative = PathBuf::new();
+    for _ in from.components().skip(common) {
+        relative.push("..");
+    }
+    for component in target.components().skip(common) {
+        relative.push(component);
+    }
+
+    let relative = relative.to_string_lossy().replace('\\', "/");
+    if relative.starts_with("..") {
+        relative
+    } else {
+        format!("./{relative}")
+    }
+}
+
+/// Lexically remove `.` and `..` components.
+fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            other => normalized.push(other),
+        }
+    }
+    normalized
+}
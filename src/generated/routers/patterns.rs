@@ -0,0 +1,247 @@
+use std::path::{Component, Path, PathBuf};
+
+use eyre::{eyre, Result};
+use foundry_compilers::Project;
+use globset::{GlobBuilder, GlobMatcher};
+use itertools::Itertools;
+use serde::Serialize;
+
+use super::{
+    scaffold::{collect_solidity_files, scan_modules},
+    ModuleEntry,
+};
+
+/// The modules a pattern of a module list expanded to.
+#[derive(Debug, Clone, Serialize)]
+pub struct Expansion {
+    pub pattern: String,
+    pub modules: Vec<String>,
+}
+
+/// Whether a module list entry is a pattern rather than a module identifier.
+///
+/// Patterns contain glob characters, e.g. `src/modules/**/*.sol` or `src/modules/*.sol:*Module`,
+/// and exclusions start with `!`.
+pub fn is_pattern(entry: &str) -> bool {
+    entry.starts_with('!') || entry.contains(['*', '?', '[', '{'])
+}
+
+/// A `<path glob>[:<name glob>]` pattern.
+struct ModulePattern {
+    path: GlobMatcher,
+    name: Option<GlobMatcher>,
+    /// Directory the path glob is rooted at, the part before its first glob character.
+    base: PathBuf,
+}
+
+impl ModulePattern {
+    fn parse(pattern: &str) -> Result<Self> {
+        let (path, name) = match pattern.rsplit_once(':') {
+            Some((path, name)) => (path, Some(name)),
+            None => (pattern, None),
+        };
+
+        let glob = |glob: &str| -> Result<GlobMatcher> {
+            Ok(GlobBuilder::new(glob)
+                .literal_separator(true)
+                .build()
+                .map_err(|e| eyre!("Invalid module pattern `{pattern}`: {e}"))?
+                .compile_matcher())
+        };
+
+        let base = Path::new(path)
+            .components()
+            .take_while(|c| match c {
+                Component::Normal(part) => !is_pattern(&part.to_string_lossy()),
+                _ => true,
+            })
+            .collect::<PathBuf>();
+
+        Ok(Self {
+            path: glob(path)?,
+            name: name.map(glob).transpose()?,
+            base,
+        })
+    }
+
+    /// Whether a `<path>:<Name>` identifier matches the pattern.
+    fn matches(&self, identifier: &str) -> bool {
+        let (path, name) = match identifier.rsplit_once(':') {
+            Some((path, name)) => (path, name),
+            None => return self.name.as_ref().is_some_and(|g| g.is_match(identifier)),
+        };
+        self.path.is_match(path) && self.name.as_ref().map_or(true, |g| g.is_match(name))
+    }
+}
+
+/// Expand the patterns of a module list against the project's files.
+///
+/// Plain identifiers are kept as they are. Patterns are replaced by the `<path>:<Name>`
+/// identifiers of the deployable contracts declared in matching files, and exclusions (`!`
+/// followed by a pattern) remove matching identifiers from the whole list. Returns the module
+/// list and what each pattern expanded to.
+///
+/// Matching files are compiled to tell deployable contracts apart, which requires the AST to be
+/// part of the compiler's output.
+pub fn expand_modules(
+    project: &Project,
+    entries: &[ModuleEntry],
+) -> Result<(Vec<ModuleEntry>, Vec<Expansion>)> {
+    let mut modules = vec![];
+    let mut expansions = vec![];
+    let mut exclusions = vec![];
+
+    for entry in entries {
+        let pattern = &entry.contract;
+        if is_pattern(pattern) {
+            if let Some(alias) = &entry.alias {
+                return Err(eyre!(
+                    "Module pattern `{pattern}` cannot be aliased, alias `{alias}` names one module"
+                ));
+            }
+        }
+
+        if let Some(exclusion) = pattern.strip_prefix('!') {
+            exclusions.push((pattern, ModulePattern::parse(exclusion)?));
+        } else if is_pattern(pattern) {
+            let expanded = expand_pattern(project, pattern)?;
+            if expanded.is_empty() {
+                return Err(eyre!("Module pattern `{pattern}` matches no contract"));
+            }
+            modules.extend(expanded.iter().cloned().map(ModuleEntry::from));
+            expansions.push(Expansion {
+                pattern: pattern.clone(),
+                modules: expanded,
+            });
+        } else {
+            modules.push(entry.clone());
+        }
+    }
+
+    for (pattern, exclusion) in exclusions {
+        let (excluded, kept): (Vec<ModuleEntry>, Vec<ModuleEntry>) = modules
+            .into_iter()
+            .partition(|m| exclusion.matches(&m.contract));
+        modules = kept;
+        expansions.push(Expansion {
+            pattern: pattern.clone(),
+            modules: excluded.into_iter().map(|m| m.contract).collect(),
+        });
+    }
+
+    Ok((modules.into_iter().unique().collect(), expansions))
+}
+
+fn expand_pattern(project: &Project, pattern: &str) -> Result<Vec<String>> {
+    let pattern = ModulePattern::parse(pattern)?;
+
+    let mut files = vec![];
+    let base = project.root().join(&pattern.base);
+    if base.exists() {
+        collect_solidity_files(&base, &mut files)?;
+    }
+
+    let files = files
+        .into_iter()
+        .filter(|file| {
+            let relative = file.strip_prefix(project.root()).unwrap_or(file);
+            pattern
+                .path
+                .is_match(relative.to_string_lossy().replace('\\', "/"))
+        })
+        .sorted()
+        .collect::<Vec<PathBuf>>();
+
+    if files.is_empty() {
+        return Ok(vec![]);
+    }
+
+    // Interfaces, libraries and abstract contracts are told apart through the AST.
+    let output = project.compile_files(files.clone())?;
+    if output.has_compiler_errors() {
+        return Err(eyre!("{output}\nCompilation failed"));
+    }
+
+    Ok(scan_modules(project, &output, &files)?
+        .into_iter()
+        .map(|module| module.identifier)
+        .filter(|identifier| pattern.matches(identifier))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use foundry_config::Config;
+
+    use super::*;
+
+    fn entries(entries: &[&str]) -> Vec<ModuleEntry> {
+        entries.iter().map(|e| ModuleEntry::new(*e)).collect()
+    }
+
+    #[test]
+    fn detects_patterns() {
+        assert!(is_pattern("src/modules/*.sol"));
+        assert!(is_pattern("src/modules/**/*.sol:*Module"));
+        assert!(is_pattern("src/modules/{Foo,Bar}.sol"));
+        assert!(is_pattern("!src/modules/Legacy.sol:Legacy"));
+        assert!(!is_pattern("src/modules/Foo.sol:Foo"));
+        assert!(!is_pattern("Foo"));
+    }
+
+    #[test]
+    fn matches_paths_and_names() {
+        let pattern = ModulePattern::parse("src/modules/*.sol:*Module").unwrap();
+        assert_eq!(pattern.base, PathBuf::from("src/modules"));
+        assert!(pattern.matches("src/modules/Counter.sol:CounterModule"));
+        assert!(!pattern.matches("src/modules/Counter.sol:Counter"));
+        assert!(!pattern.matches("src/modules/nested/Counter.sol:CounterModule"));
+        assert!(pattern.matches("CounterModule"));
+
+        let pattern = ModulePattern::parse("src/modules/**/*.sol").unwrap();
+        assert!(pattern.matches("src/modules/nested/Counter.sol:Counter"));
+        assert!(!pattern.matches("src/other/Counter.sol:Counter"));
+        assert!(!pattern.matches("Counter"));
+
+        assert_eq!(ModulePattern::parse("*.sol").unwrap().base, PathBuf::new());
+        assert!(ModulePattern::parse("src/[.sol").is_err());
+    }
+
+    #[test]
+    fn exclusions_apply_to_the_whole_list() {
+        let project = Config::with_root(std::env::temp_dir()).project().unwrap();
+        let (modules, expansions) = expand_modules(
+            &project,
+            &entries(&[
+                "!src/modules/Legacy*.sol",
+                "src/modules/Counter.sol:Counter",
+                "src/modules/LegacyCounter.sol:LegacyCounter",
+                "src/modules/Counter.sol:Counter",
+            ]),
+        )
+        .unwrap();
+
+        assert_eq!(modules, entries(&["src/modules/Counter.sol:Counter"]));
+        assert_eq!(expansions.len(), 1);
+        assert_eq!(expansions[0].pattern, "!src/modules/Legacy*.sol");
+        assert_eq!(
+            expansions[0].modules,
+            ["src/modules/LegacyCounter.sol:LegacyCounter"]
+        );
+    }
+
+    #[test]
+    fn rejects_aliased_and_unmatched_patterns() {
+        let root = std::env::temp_dir().join("cannon-rs-expand-modules");
+        let project = Config::with_root(root).project().unwrap();
+
+        let aliased = ModuleEntry {
+            contract: "src/modules/*.sol".to_string(),
+            alias: Some("Counter".to_string()),
+        };
+        assert!(expand_modules(&project, &[aliased]).is_err());
+
+        let unmatched = entries(&["src/missing/*.sol"]);
+        assert!(expand_modules(&project, &unmatched).is_err());
+    }
+}
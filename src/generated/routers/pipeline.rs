@@ -0,0 +1,460 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs, io,
+    path::PathBuf,
+};
+
+use alloy_primitives::{Address, B256};
+use eyre::{eyre, Result};
+use foundry_compilers::{
+    artifacts::output_selection::ContractOutputSelection, compilers::CompilationError, Project,
+    ProjectCompileOutput,
+};
+use foundry_config::Config;
+use serde::Serialize;
+
+use super::{
+    collect_sources, deterministric, facade,
+    features::FEATURES_NAMESPACE,
+    find_artifacts,
+    guards::{permission_matrix, Permission},
+    identify_sources, immutable,
+    inspect::{inspect_router, RouterInfo},
+    lint::{check_findings, lint_modules},
+    module_contracts, naming,
+    output::output_path,
+    patterns::is_pattern,
+    proxy::check_selector_clashes,
+    registry, router_sources,
+    script::{render_deploy_script, script_path},
+    storage::{
+        check_storage_collisions, compare_layouts, erc7201_namespaces, snapshot_path,
+        storage_snapshot, LayoutChange, StorageSnapshot,
+    },
+    toml::Router,
+    upgradeable, ModuleEntry,
+};
+
+/// The kind of router generated from a spec.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Flavor {
+    /// Router delegating to modules deployed with CREATE2 from `deployer`.
+    Deterministic { deployer: Address, salt: B256 },
+    /// The deterministic router as a standalone Yul object.
+    DeterministicYul { deployer: Address, salt: B256 },
+    /// Router delegating to module addresses set at construction.
+    Immutable,
+    /// UUPS router behind an ERC-1967 proxy, modules deployed with CREATE2 from `deployer`.
+    Upgradeable { deployer: Address, salt: B256 },
+    /// Router resolving modules from an on-chain registry.
+    Registry,
+    /// Facade forwarding calls to independently deployed modules.
+    Facade,
+}
+
+impl Flavor {
+    /// Extension of the generated file.
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::DeterministicYul { .. } => "yul",
+            _ => "sol",
+        }
+    }
+
+    /// The CREATE2 deployer and salt module addresses are computed from, if any.
+    pub fn create2(&self) -> Option<(Address, B256)> {
+        match *self {
+            Self::Deterministic { deployer, salt }
+            | Self::DeterministicYul { deployer, salt }
+            | Self::Upgradeable { deployer, salt } => Some((deployer, salt)),
+            _ => None,
+        }
+    }
+
+    /// Identifiers the generated router declares, which module constants must not take.
+    pub fn router_identifiers(&self) -> &'static [&'static str] {
+        match self {
+            Self::Deterministic { .. } => deterministric::ROUTER_IDENTIFIERS,
+            Self::DeterministicYul { .. } => deterministric::YUL_ROUTER_IDENTIFIERS,
+            Self::Immutable => immutable::ROUTER_IDENTIFIERS,
+            Self::Upgradeable { .. } => upgradeable::ROUTER_IDENTIFIERS,
+            Self::Registry => registry::ROUTER_IDENTIFIERS,
+            Self::Facade => facade::ROUTER_IDENTIFIERS,
+        }
+    }
+
+    /// Whether modules run in the router's context through `delegatecall`, sharing its storage.
+    pub fn delegates(&self) -> bool {
+        !matches!(self, Self::Facade)
+    }
+
+    /// Whether a deployment script can be generated for the flavor.
+    pub fn has_deploy_script(&self) -> bool {
+        matches!(self, Self::Deterministic { .. } | Self::Immutable)
+    }
+}
+
+/// A router to generate.
+#[derive(Debug, Clone)]
+pub struct RouterSpec {
+    pub name: String,
+    pub router: Router,
+    pub flavor: Flavor,
+}
+
+impl RouterSpec {
+    pub fn new(name: String, router: Router, flavor: Flavor) -> Self {
+        Self {
+            name,
+            router,
+            flavor,
+        }
+    }
+
+    /// Fail on options the flavor does not support, before anything is compiled or written.
+    pub fn validate(&self) -> Result<()> {
+        let yul = matches!(self.flavor, Flavor::DeterministicYul { .. });
+        if yul && !self.router.dispatch.is_empty() {
+            return Err(eyre!(
+                "Hooks, guards and features are not supported in Yul output for `{}`",
+                self.name
+            ));
+        }
+        Ok(())
+    }
+
+    /// Where the generated router is written.
+    pub fn path(&self, project: &Project) -> PathBuf {
+        output_path(
+            project,
+            self.router.output.as_deref(),
+            &self.name,
+            self.flavor.extension(),
+        )
+    }
+}
+
+/// A generated router, not yet written.
+#[derive(Debug, Clone, Serialize)]
+pub struct GeneratedRouter {
+    #[serde(skip)]
+    pub name: String,
+    pub flavor: Flavor,
+    pub path: PathBuf,
+    #[serde(skip)]
+    pub source: String,
+    /// Modules, selectors and dispatch tree depth of the router.
+    #[serde(flatten)]
+    pub info: RouterInfo,
+    /// Who may call each selector, empty for routers without guards.
+    pub permissions: Vec<Permission>,
+    /// Compiler warnings in the router's modules and other issues worth a look.
+    pub warnings: Vec<String>,
+    /// Module storage changes incompatible with the router's snapshot.
+    pub storage_changes: Vec<LayoutChange>,
+    /// The current module layouts and where to save them, for routers with a snapshot.
+    #[serde(skip)]
+    pub storage_snapshot: Option<(PathBuf, StorageSnapshot)>,
+    /// The forge script deploying the router and where to write it, when requested.
+    #[serde(skip)]
+    pub deploy_script: Option<(PathBuf, String)>,
+}
+
+/// Enable the compiler outputs the specs rely on.
+pub fn configure(config: &mut Config, specs: &[RouterSpec]) {
+    // Hooks are validated against the AST of their libraries, modules are linted on theirs, the
+    // modules of upgradeable routers are checked for writes to the proxy's slots and module
+    // patterns expand to the deployable contracts it declares.
+    config.ast |= specs.iter().any(|s| {
+        !s.router.dispatch.hooks.is_empty()
+            || (s.flavor.delegates() && s.router.lint.is_enabled())
+            || matches!(s.flavor, Flavor::Upgradeable { .. })
+            || s.router.modules.iter().any(|m| is_pattern(&m.contract))
+    });
+
+    // Module storage layouts are checked for collisions with each other when modules share the
+    // router's storage, and against snapshots.
+    let storage_layout = specs
+        .iter()
+        .any(|s| s.flavor.delegates() || s.router.storage_snapshot.is_some());
+    if storage_layout
+        && !config
+            .extra_output
+            .contains(&ContractOutputSelection::StorageLayout)
+    {
+        config
+            .extra_output
+            .push(ContractOutputSelection::StorageLayout);
+    }
+}
+
+/// Compile the modules and hook libraries of every spec at once.
+pub fn compile(project: &Project, specs: &[RouterSpec]) -> Result<ProjectCompileOutput> {
+    let mut contracts = vec![];
+    for spec in specs {
+        contracts.extend(spec.router.contracts()?);
+    }
+
+    let sources = identify_sources(project, contracts)?;
+
+    let output = project.compile_files(sources)?;
+
+    if output.has_compiler_errors() {
+        return Err(eyre!("{output}\nCompilation failed"));
+    }
+
+    Ok(output)
+}
+
+/// Generate every spec from a compiled project.
+pub fn generate_routers(
+    project: &Project,
+    output: &ProjectCompileOutput,
+    specs: &[RouterSpec],
+) -> Result<Vec<GeneratedRouter>> {
+    specs
+        .iter()
+        .map(|spec| generate(project, output, spec))
+        .collect()
+}
+
+/// Compile and generate every spec.
+pub fn run_pipeline(project: &Project, specs: &[RouterSpec]) -> Result<Vec<GeneratedRouter>> {
+    specs.iter().try_for_each(RouterSpec::validate)?;
+
+    let output = compile(project, specs)?;
+    generate_routers(project, &output, specs)
+}
+
+fn generate(
+    project: &Project,
+    output: &ProjectCompileOutput,
+    spec: &RouterSpec,
+) -> Result<GeneratedRouter> {
+    let path = spec.path(project);
+    let name = spec.name.clone();
+    let router = &spec.router;
+    let modules = router.modules.clone();
+    let unknown_selector = &router.unknown_selector;
+    let dispatch = router.dispatch.with_output(&path);
+
+    // Delegatecalled modules share the router's storage, their layouts and namespaces must not
+    // overlap.
+    // Facade modules are called and keep their own storage.
+    if spec.flavor.delegates() {
+        let namespaces = modules
+            .iter()
+            .map(|m| {
+                let sources = router_sources(project, vec![m.contract.clone()])?;
+                Ok((m.name(), erc7201_namespaces(&sources)?))
+            })
+            .collect::<Result<BTreeMap<String, BTreeSet<String>>>>()?;
+        check_storage_collisions(
+            &find_artifacts(project, output, modules.clone())?,
+            &namespaces,
+            &[FEATURES_NAMESPACE],
+        )?;
+    }
+
+    let (selectors, _) = collect_sources(
+        project,
+        output,
+        modules.clone(),
+        None,
+        None,
+        spec.flavor.router_identifiers(),
+    )?;
+    check_selector_clashes(&selectors, router.proxy, &router.reserved_selectors)?;
+
+    // Facade modules run in their own context, the delegatecall-safety rules do not apply.
+    let lint_warnings = match spec.flavor.delegates() {
+        true => check_findings(lint_modules(
+            project,
+            output,
+            modules.clone(),
+            &router.lint,
+        )?)?,
+        false => vec![],
+    };
+
+    let source = match spec.flavor {
+        Flavor::Deterministic { deployer, salt } => deterministric::generate_router(
+            project,
+            output,
+            name,
+            modules,
+            deployer,
+            salt,
+            unknown_selector,
+            &dispatch,
+        )?,
+        Flavor::DeterministicYul { deployer, salt } => deterministric::generate_yul_router(
+            project,
+            output,
+            name,
+            modules,
+            deployer,
+            salt,
+            unknown_selector,
+            &dispatch,
+        )?,
+        Flavor::Immutable => {
+            immutable::generate_router(project, output, name, modules, unknown_selector, &dispatch)?
+        }
+        Flavor::Upgradeable { deployer, salt } => upgradeable::generate_router(
+            project,
+            output,
+            name,
+            modules,
+            deployer,
+            salt,
+            unknown_selector,
+            &dispatch,
+        )?,
+        Flavor::Registry => registry::generate_router(
+            project,
+            output,
+            name,
+            modules,
+            router.cache,
+            unknown_selector,
+            &dispatch,
+        )?,
+        Flavor::Facade => {
+            facade::generate_router(project, output, name, modules, unknown_selector, &dispatch)?
+        }
+    };
+
+    let permissions = match router.dispatch.guards.is_empty() {
+        true => vec![],
+        false => permission_matrix(&router.dispatch.guards, &selectors)?,
+    };
+
+    let (storage_changes, storage_snapshot) = check_storage(project, output, spec)?;
+
+    // Standalone Yul routers share their modules with the Solidity router of the same name,
+    // whose script deploys them.
+    let mut script_warnings = vec![];
+    let deploy_script = match router.deploy_script && spec.flavor.has_deploy_script() {
+        true => Some((
+            script_path(project, &spec.name),
+            render_deploy_script(project, output, spec)?,
+        )),
+        false => {
+            if router.deploy_script && !matches!(spec.flavor, Flavor::DeterministicYul { .. }) {
+                script_warnings.push(
+                    "Deployment scripts are only generated for deterministic and immutable \
+                     routers, skipped"
+                        .to_string(),
+                );
+            }
+            None
+        }
+    };
+
+    let info = inspect_router(
+        project,
+        output,
+        spec.name.clone(),
+        router.modules.clone(),
+        spec.flavor,
+    )?;
+
+    Ok(GeneratedRouter {
+        name: spec.name.clone(),
+        flavor: spec.flavor,
+        path,
+        source,
+        info,
+        permissions,
+        warnings: [
+            warnings(project, output, &router.modules, spec.flavor)?,
+            lint_warnings,
+            script_warnings,
+        ]
+        .concat(),
+        storage_changes,
+        storage_snapshot,
+        deploy_script,
+    })
+}
+
+/// The current layouts of the spec's modules and their incompatible changes since the saved
+/// snapshot. A missing snapshot file has no changes, other read errors are returned.
+fn check_storage(
+    project: &Project,
+    output: &ProjectCompileOutput,
+    spec: &RouterSpec,
+) -> Result<(Vec<LayoutChange>, Option<(PathBuf, StorageSnapshot)>)> {
+    let Some(snapshot) = &spec.router.storage_snapshot else {
+        return Ok((vec![], None));
+    };
+
+    let path = snapshot_path(project, snapshot, &spec.name);
+    let modules = find_artifacts(project, output, spec.router.modules.clone())?;
+    let current = storage_snapshot(&modules)?;
+
+    let changes = match fs::read_to_string(&path) {
+        Ok(content) => {
+            let previous: StorageSnapshot = serde_json::from_str(&content)
+                .map_err(|e| eyre!("Invalid storage snapshot `{}`: {e}", path.display()))?;
+            compare_layouts(&previous, &current)
+        }
+        Err(e) if e.kind() == io::ErrorKind::NotFound => vec![],
+        Err(e) => {
+            return Err(eyre!(
+                "Could not read storage snapshot `{}`: {e}",
+                path.display()
+            ))
+        }
+    };
+
+    Ok((changes, Some((path, current))))
+}
+
+/// Compiler warnings raised in the modules' sources, renamed module identifiers and modules
+/// without any routed function.
+fn warnings(
+    project: &Project,
+    output: &ProjectCompileOutput,
+    module_names: &[ModuleEntry],
+    flavor: Flavor,
+) -> Result<Vec<String>> {
+    let mut warnings = vec![];
+
+    let sources = identify_sources(project, module_contracts(module_names))?;
+    for warning in output.output().errors.iter().filter(|e| e.is_warning()) {
+        let in_module = warning.source_location().is_some_and(|location| {
+            let file = project.root().join(&location.file);
+            sources.iter().any(|source| *source == file)
+        });
+        if in_module {
+            warnings.push(warning.to_string().trim().to_string());
+        }
+    }
+
+    let artifacts = find_artifacts(project, output, module_names.to_vec())?;
+
+    // Identifiers that could not be derived from the contract names as is.
+    let contract_names = artifacts
+        .iter()
+        .map(|(name, _)| name.clone())
+        .collect::<Vec<_>>();
+    let (_, renames) = naming::module_names(&contract_names, flavor.router_identifiers());
+    warnings.extend(renames.iter().map(|r| r.to_string()));
+
+    for (name, artifact) in artifacts {
+        if artifact
+            .abi
+            .as_ref()
+            .map_or(true, |abi| abi.functions.is_empty())
+        {
+            warnings.push(format!(
+                "Module `{name}` has no external functions to route"
+            ));
+        }
+    }
+
+    Ok(warnings)
+}
@@ -0,0 +1,198 @@
+use std::{collections::HashMap, fmt};
+
+use alloy_primitives::{hex::ToHexExt, Selector};
+use eyre::{eyre, Result};
+use serde::Deserialize;
+
+use super::{scope::parse_selector, Module};
+
+/// Functions of OpenZeppelin's transparent proxy, dispatched for its admin only.
+const TRANSPARENT_FUNCTIONS: [&str; 5] = [
+    "admin()",
+    "implementation()",
+    "changeAdmin(address)",
+    "upgradeTo(address)",
+    "upgradeToAndCall(address,bytes)",
+];
+
+/// Functions of UUPS implementations, routed to the router itself.
+const UUPS_FUNCTIONS: [&str; 5] = [
+    "proxiableUUID()",
+    "changeAdmin(address)",
+    "upgradeTo(address)",
+    "upgradeToAndCall(address,bytes)",
+    "UPGRADE_INTERFACE_VERSION()",
+];
+
+/// Getters commonly exposed by EIP-1967 proxies.
+const EIP1967_FUNCTIONS: [&str; 5] = [
+    "implementation()",
+    "admin()",
+    "getImplementation()",
+    "getAdmin()",
+    "getBeacon()",
+];
+
+/// The proxy a router is deployed behind.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProxyKind {
+    /// Transparent proxy whose admin functions shadow the router's.
+    Transparent,
+    /// UUPS proxy, the upgrade functions live in the implementation.
+    Uups,
+    /// EIP-1967 proxy exposing getters for its slots.
+    Eip1967,
+}
+
+impl ProxyKind {
+    /// The signatures of the functions the proxy implements itself.
+    pub fn functions(&self) -> &'static [&'static str] {
+        match self {
+            Self::Transparent => &TRANSPARENT_FUNCTIONS,
+            Self::Uups => &UUPS_FUNCTIONS,
+            Self::Eip1967 => &EIP1967_FUNCTIONS,
+        }
+    }
+}
+
+impl fmt::Display for ProxyKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Self::Transparent => "transparent proxy",
+            Self::Uups => "UUPS proxy",
+            Self::Eip1967 => "EIP-1967 proxy",
+        };
+        f.write_str(name)
+    }
+}
+
+/// A selector the router must not route, with what reserves it.
+#[derive(Debug, Clone)]
+struct Reserved {
+    selector: Selector,
+    signature: Option<String>,
+    owner: String,
+}
+
+/// Fail if a routed selector clashes with a function of the proxy wrapping the router or a
+/// user-reserved selector.
+///
+/// A clash either shadows the routed function behind the proxy or makes the proxy's function
+/// unreachable, depending on which one the proxy dispatches first.
+pub fn check_selector_clashes(
+    selectors: &HashMap<Selector, Module>,
+    proxy: Option<ProxyKind>,
+    reserved_selectors: &[String],
+) -> Result<()> {
+    let mut reserved = vec![];
+    if let Some(proxy) = proxy {
+        for signature in proxy.functions() {
+            reserved.push(Reserved {
+                selector: parse_selector(signature)?,
+                signature: Some(signature.to_string()),
+                owner: proxy.to_string(),
+            });
+        }
+    }
+    for entry in reserved_selectors {
+        reserved.push(Reserved {
+            selector: parse_selector(entry)?,
+            // Selectors given as hex have no signature to show.
+            signature: (!entry.starts_with("0x")).then(|| entry.clone()),
+            owner: "reserved selectors".to_string(),
+        });
+    }
+
+    let mut clashes = reserved
+        .iter()
+        .filter_map(|r| {
+            let module = selectors.get(&r.selector)?;
+            let reserved_by = match &r.signature {
+                Some(signature) => format!("{} `{signature}`", r.owner),
+                None => r.owner.clone(),
+            };
+            Some(format!(
+                "{} {}.{} clashes with {reserved_by}",
+                r.selector.encode_hex_with_prefix(),
+                module.contract_name,
+                module.signature
+            ))
+        })
+        .collect::<Vec<String>>();
+    clashes.sort();
+    clashes.dedup();
+
+    if !clashes.is_empty() {
+        return Err(eyre!(
+            "Routed selectors clash with the proxy or reserved selectors:\n  {}",
+            clashes.join("\n  ")
+        ));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy_json_abi::Function;
+
+    use super::super::tests::{module, routed};
+    use super::*;
+
+    fn selectors() -> HashMap<Selector, Module> {
+        routed([
+            module("src/Upgrades.sol:Upgrades", "upgradeTo(address)"),
+            module("src/Ownable.sol:Ownable", "owner()"),
+        ])
+    }
+
+    #[test]
+    fn rejects_uups_clashes() {
+        let error = check_selector_clashes(&selectors(), Some(ProxyKind::Uups), &[])
+            .unwrap_err()
+            .to_string();
+        assert!(error.contains("Upgrades.upgradeTo(address) clashes with UUPS proxy"));
+
+        let selectors = routed([module("src/Admin.sol:Admin", "changeAdmin(address)")]);
+        let error = check_selector_clashes(&selectors, Some(ProxyKind::Uups), &[])
+            .unwrap_err()
+            .to_string();
+        assert!(error.contains("Admin.changeAdmin(address) clashes with UUPS proxy"));
+    }
+
+    #[test]
+    fn rejects_transparent_proxy_clashes() {
+        let selectors = routed([module("src/Admin.sol:Admin", "admin()")]);
+        let error = check_selector_clashes(&selectors, Some(ProxyKind::Transparent), &[])
+            .unwrap_err()
+            .to_string();
+        assert!(error.contains("Admin.admin() clashes with transparent proxy `admin()`"));
+    }
+
+    #[test]
+    fn passes_without_clashes() {
+        let selectors = routed([module("src/Ownable.sol:Ownable", "owner()")]);
+        for proxy in [
+            None,
+            Some(ProxyKind::Transparent),
+            Some(ProxyKind::Uups),
+            Some(ProxyKind::Eip1967),
+        ] {
+            check_selector_clashes(&selectors, proxy, &["pause()".to_string()]).unwrap();
+        }
+    }
+
+    #[test]
+    fn rejects_reserved_selectors() {
+        let owner = Function::parse("owner()").unwrap().selector();
+
+        for entry in ["owner()".to_string(), owner.encode_hex_with_prefix()] {
+            let error = check_selector_clashes(&selectors(), None, &[entry])
+                .unwrap_err()
+                .to_string();
+            assert!(error.contains("Ownable.owner() clashes with reserved selectors"));
+        }
+        assert!(check_selector_clashes(&selectors(), None, &["0x1234".to_string()]).is_err());
+    }
+}
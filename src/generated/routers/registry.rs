@@ -0,0 +1,116 @@
+use alloy_json_abi::{JsonAbi, StateMutability};
+use eyre::Result;
+use foundry_compilers::{Project, ProjectCompileOutput};
+
+use super::{
+    render_modules_with_template, unknown::UnknownSelector, DispatchOptions, Module, ModuleEntry,
+};
+
+/// Identifiers the router and its resolver declare next to the module keys.
+pub const ROUTER_IDENTIFIERS: &[&str] = &["FEATURES_SLOT", "REGISTRY", "CACHE_NAMESPACE"];
+
+/// Generate a router that resolves module addresses from an on-chain registry.
+///
+/// When `cache` is set, resolved addresses are kept in transient storage for the rest of the
+/// transaction. Transient writes are not allowed in a static context, so view and pure functions
+/// and forwarded unknown selectors are resolved without filling the cache, keeping them callable
+/// through `staticcall`.
+pub fn generate_router(
+    project: &Project,
+    output: &ProjectCompileOutput,
+    router_name: String,
+    module_names: Vec<ModuleEntry>,
+    cache: bool,
+    unknown_selector: &UnknownSelector,
+    dispatch: &DispatchOptions,
+) -> Result<String> {
+    super::generate_router(
+        project,
+        output,
+        router_name,
+        module_names,
+        None,
+        None,
+        ROUTER_IDENTIFIERS,
+        dispatch,
+        &|m: &Module| match cache && is_read_only(m) {
+            true => format!("result := {} readOnly := 1", m.constant_name),
+            false => format!("result := {}", m.constant_name),
+        },
+        &|router_name: &String, modules: &Vec<Module>, abi: &JsonAbi| {
+            template(router_name, modules, abi, cache, unknown_selector)
+        },
+    )
+}
+
+fn template(
+    router_name: &String,
+    modules: &Vec<Module>,
+    abi: &JsonAbi,
+    cache: bool,
+    unknown_selector: &UnknownSelector,
+) -> Result<String> {
+    let abi = unknown_selector.extend_abi(abi)?;
+    let interface = abi.to_sol(format!("I{router_name}").as_str(), None);
+
+    let module_lookup = render_modules_with_template(modules, &|m| {
+        format!(
+            "    bytes32 constant {} = {};",
+            m.constant_name, m.contract_identifier
+        )
+    });
+
+    // Transient storage requires Cancun support in the compiler.
+    let (pragma, resolver) = if cache {
+        (
+            "^0.8.24",
+            include_str!("../../../assets/templates/RegistryCachedResolver.sol"),
+        )
+    } else {
+        (
+            "^0.8.13",
+            include_str!("../../../assets/templates/RegistryResolver.sol"),
+        )
+    };
+
+    // Forwarded unknown selectors may be view functions, they are never cached.
+    let (read_only, read_only_declaration, forwarded_read_only) = match cache {
+        true => (", readOnly", "        bool readOnly;\n", ", true"),
+        false => ("", "", ""),
+    };
+
+    let unknown_selector_error = unknown_selector.render_declaration()?;
+    let unknown_selector_fallback = unknown_selector.render_fallback(
+        modules,
+        |m| {
+            format!(
+                "findImplementationAddress({}{forwarded_read_only})",
+                m.constant_name
+            )
+        },
+        |target| format!("implementation_address = {target};"),
+    )?;
+
+    // Create the router file content.
+    let router_content = include_str!("../../../assets/templates/RegistryRouterTemplate.sol");
+    let router_content = router_content
+        .replace("{pragma}", pragma)
+        .replace("{resolver}", resolver)
+        .replace("{interface}", &interface)
+        .replace("{router_name}", &router_name)
+        .replace("{modules}", &module_lookup)
+        .replace("{read_only_declaration}\n", read_only_declaration)
+        .replace("{read_only}", read_only)
+        .replace("{unknown_selector_error}\n", &unknown_selector_error)
+        .replace("{unknown_selector}", &unknown_selector_fallback);
+
+    Ok(router_content)
+}
+
+/// Whether the module function may be reached through `staticcall`.
+fn is_read_only(module: &Module) -> bool {
+    matches!(
+        module.state_mutability,
+        StateMutability::View | StateMutability::Pure
+    )
+}
@@ -0,0 +1,262 @@
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use alloy_json_abi::JsonAbi;
+use alloy_primitives::Selector;
+use eyre::{eyre, Result};
+use foundry_compilers::{artifacts::ast::NodeType, Project, ProjectCompileOutput};
+use itertools::Itertools;
+use serde::Serialize;
+
+/// A deployable contract found while scanning the project.
+#[derive(Debug, Clone, Serialize)]
+pub struct ScannedModule {
+    pub name: String,
+    /// `<path>:<Name>` identifier, relative to the project root.
+    pub identifier: String,
+    /// Selectors and signatures of the module's external functions.
+    pub functions: BTreeMap<Selector, String>,
+}
+
+/// A selector implemented by more than one scanned module.
+#[derive(Debug, Clone, Serialize)]
+pub struct SelectorCollision {
+    pub selector: Selector,
+    pub signature: String,
+    pub modules: Vec<String>,
+}
+
+/// Collect the Solidity files under the given files or directories, skipping tests, scripts and
+/// generated routers.
+pub fn scan_sources(project: &Project, paths: &[String]) -> Result<Vec<PathBuf>> {
+    let mut sources = vec![];
+    for path in paths {
+        let path = project.root().join(path);
+        if !path.exists() {
+            return Err(eyre!("`{}` does not exist", path.display()));
+        }
+        collect_solidity_files(&path, &mut sources)?;
+    }
+
+    let generated = project.sources_path().join("generated");
+    Ok(sources
+        .into_iter()
+        .filter(|p| !p.starts_with(&generated))
+        .filter(|p| {
+            let name = p.to_string_lossy();
+            !name.ends_with(".t.sol") && !name.ends_with(".s.sol")
+        })
+        .sorted()
+        .dedup()
+        .collect())
+}
+
+pub(super) fn collect_solidity_files(path: &Path, files: &mut Vec<PathBuf>) -> Result<()> {
+    if path.is_dir() {
+        for entry in fs::read_dir(path)? {
+            collect_solidity_files(&entry?.path(), files)?;
+        }
+    } else if path.extension().is_some_and(|ext| ext == "sol") {
+        files.push(path.to_path_buf());
+    }
+    Ok(())
+}
+
+/// Find the deployable contracts defined in the given sources, sorted by identifier.
+///
+/// Interfaces, libraries and abstract contracts are skipped. Requires the AST to be part of the
+/// compiler's output.
+pub fn scan_modules(
+    project: &Project,
+    output: &ProjectCompileOutput,
+    sources: &[PathBuf],
+) -> Result<Vec<ScannedModule>> {
+    let mut modules = vec![];
+
+    for (path, name, artifact) in output.clone().into_artifacts_with_files() {
+        let absolute = project.root().join(&path);
+        if !sources.contains(&absolute) && !sources.contains(&path) {
+            continue;
+        }
+
+        let ast = artifact
+            .ast
+            .as_ref()
+            .ok_or_else(|| eyre!("No AST found for `{name}`, enable `ast`"))?;
+
+        let deployable = ast
+            .nodes
+            .iter()
+            .filter(|n| n.node_type == NodeType::ContractDefinition)
+            .find(|n| n.other.get("name").and_then(|v| v.as_str()) == Some(name.as_str()))
+            .is_some_and(|n| {
+                n.other.get("contractKind").and_then(|v| v.as_str()) == Some("contract")
+                    && n.other.get("abstract").and_then(|v| v.as_bool()) == Some(false)
+            });
+
+        if !deployable {
+            continue;
+        }
+
+        let relative = path.strip_prefix(project.root()).unwrap_or(&path);
+        let abi: &JsonAbi = artifact
+            .abi
+            .as_ref()
+            .ok_or_else(|| eyre!("No ABI found for `{name}`"))?;
+
+        modules.push(ScannedModule {
+            identifier: format!("{}:{name}", relative.to_string_lossy().replace('\\', "/")),
+            functions: abi
+                .functions()
+                .map(|f| (f.selector(), f.signature()))
+                .collect(),
+            name,
+        });
+    }
+
+    Ok(modules
+        .into_iter()
+        .sorted_by(|a, b| a.identifier.cmp(&b.identifier))
+        .dedup_by(|a, b| a.identifier == b.identifier)
+        .collect())
+}
+
+/// Every selector implemented by more than one module.
+pub fn find_collisions(modules: &[ScannedModule]) -> Vec<SelectorCollision> {
+    let mut implementers = BTreeMap::<Selector, (String, Vec<String>)>::new();
+    for module in modules {
+        for (selector, signature) in &module.functions {
+            implementers
+                .entry(*selector)
+                .or_insert_with(|| (signature.clone(), vec![]))
+                .1
+                .push(module.name.clone());
+        }
+    }
+
+    implementers
+        .into_iter()
+        .filter(|(_, (_, modules))| modules.len() > 1)
+        .map(|(selector, (signature, modules))| SelectorCollision {
+            selector,
+            signature,
+            modules,
+        })
+        .collect()
+}
+
+/// Group modules into as few routers as possible without selector collisions.
+///
+/// Each module goes to the first router none of whose modules share a selector with it.
+pub fn group_modules(modules: &[ScannedModule]) -> Vec<Vec<&ScannedModule>> {
+    let mut groups: Vec<Vec<&ScannedModule>> = vec![];
+
+    for module in modules {
+        let group = groups.iter_mut().find(|group| {
+            group.iter().all(|other| {
+                module
+                    .functions
+                    .keys()
+                    .all(|s| !other.functions.contains_key(s))
+            })
+        });
+
+        match group {
+            Some(group) => group.push(module),
+            None => groups.push(vec![module]),
+        }
+    }
+
+    groups
+}
+
+/// Render a router TOML definition, the first router being named `router_name` and the next
+/// ones `router_name2`, `router_name3`...
+pub fn render_toml(router_name: &str, groups: &[Vec<&ScannedModule>]) -> String {
+    groups
+        .iter()
+        .enumerate()
+        .map(|(i, group)| {
+            let name = match i {
+                0 => router_name.to_string(),
+                _ => format!("{router_name}{}", i + 1),
+            };
+            let modules = group
+                .iter()
+                .map(|m| format!("    \"{}\",", m.identifier))
+                .join("\n");
+            format!("[router.{name}]\nmodules = [\n{modules}\n]\n")
+        })
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy_json_abi::Function;
+
+    use super::*;
+
+    fn scanned(identifier: &str, signatures: &[&str]) -> ScannedModule {
+        ScannedModule {
+            name: identifier.rsplit_once(':').unwrap().1.to_string(),
+            identifier: identifier.to_string(),
+            functions: signatures
+                .iter()
+                .map(|s| {
+                    let function = Function::parse(s).unwrap();
+                    (function.selector(), function.signature())
+                })
+                .collect(),
+        }
+    }
+
+    fn modules() -> Vec<ScannedModule> {
+        vec![
+            scanned(
+                "src/Ownable.sol:Ownable",
+                &["owner()", "transferOwnership(address)"],
+            ),
+            scanned("src/Vault.sol:Vault", &["deposit(uint256)", "owner()"]),
+            scanned("src/Token.sol:Token", &["transfer(address,uint256)"]),
+        ]
+    }
+
+    #[test]
+    fn finds_selectors_shared_by_modules() {
+        let modules = modules();
+        let collisions = find_collisions(&modules);
+
+        assert_eq!(collisions.len(), 1);
+        assert_eq!(collisions[0].signature, "owner()");
+        assert_eq!(collisions[0].modules, ["Ownable", "Vault"]);
+        assert!(find_collisions(&modules[..1]).is_empty());
+    }
+
+    #[test]
+    fn groups_modules_into_collision_free_routers() {
+        let modules = modules();
+        let groups = group_modules(&modules);
+
+        let names = groups
+            .iter()
+            .map(|group| group.iter().map(|m| m.name.as_str()).collect::<Vec<&str>>())
+            .collect::<Vec<Vec<&str>>>();
+        assert_eq!(names, [vec!["Ownable", "Token"], vec!["Vault"]]);
+    }
+
+    #[test]
+    fn renders_one_router_per_group() {
+        let modules = modules();
+        let groups = group_modules(&modules);
+
+        assert_eq!(
+            render_toml("Proxy", &groups),
+            "[router.Proxy]\nmodules = [\n    \"src/Ownable.sol:Ownable\",\n    \"src/Token.sol:Token\",\n]\n\n\
+             [router.Proxy2]\nmodules = [\n    \"src/Vault.sol:Vault\",\n]\n"
+        );
+        assert_eq!(render_toml("Proxy", &[]), "");
+    }
+}
@@ -0,0 +1,118 @@
+use std::{collections::HashMap, path::Path};
+
+use alloy_json_abi::Function;
+use alloy_primitives::{hex::ToHexExt, Selector};
+use eyre::{eyre, Result};
+use foundry_compilers::info::ContractInfo;
+use itertools::Itertools;
+use serde::Deserialize;
+
+use super::Module;
+
+/// A subset of a router's selectors, given as whole modules and/or individual selectors.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct Scope {
+    /// Every selector of these modules, given as `<path>:<Name>` identifiers, aliases or contract
+    /// names shared by no other module.
+    #[serde(default)]
+    pub modules: Vec<String>,
+    /// These selectors, given as `0x` selectors or function signatures.
+    #[serde(default)]
+    pub selectors: Vec<String>,
+}
+
+impl Scope {
+    pub fn is_empty(&self) -> bool {
+        self.modules.is_empty() && self.selectors.is_empty()
+    }
+
+    /// Resolve the scope against the router's selectors, failing on anything the router does
+    /// not route. `owner` names what the scope belongs to in error messages.
+    pub fn resolve(
+        &self,
+        owner: &str,
+        selectors: &HashMap<Selector, Module>,
+    ) -> Result<Vec<Selector>> {
+        let mut scope = vec![];
+
+        for module in &self.modules {
+            let target = find_module(selectors.values(), module)
+                .map_err(|e| eyre!("{owner} is scoped to {e}"))?
+                .ok_or_else(|| {
+                    eyre!(
+                        "{owner} is scoped to `{module}`, which is not one of the router's modules"
+                    )
+                })?;
+
+            scope.extend(
+                selectors
+                    .values()
+                    .filter(|m| m.contract == target.contract)
+                    .map(|m| m.selector),
+            );
+        }
+
+        for selector in &self.selectors {
+            let parsed = parse_selector(selector)?;
+            if !selectors.contains_key(&parsed) {
+                return Err(eyre!(
+                    "{owner} is scoped to `{selector}` ({}), which is not routed by the router",
+                    parsed.encode_hex_with_prefix()
+                ));
+            }
+            scope.push(parsed);
+        }
+
+        Ok(scope.into_iter().unique().sorted().collect())
+    }
+}
+
+/// Find the module named by `reference`, either its `<path>:<Name>` identifier, its alias or its
+/// contract name. Fails when a name is shared by several modules, e.g. a contract and an aliased
+/// one of the same name.
+pub fn find_module<'a>(
+    modules: impl IntoIterator<Item = &'a Module>,
+    reference: &str,
+) -> Result<Option<&'a Module>> {
+    let target = ContractInfo::new(reference);
+
+    let found = modules
+        .into_iter()
+        .filter(|m| {
+            let contract = ContractInfo::new(&m.contract);
+            match &target.path {
+                Some(path) => {
+                    contract.name == target.name
+                        && contract
+                            .path
+                            .is_some_and(|p| Path::new(&p) == Path::new(path))
+                }
+                None => m.contract_name == reference || contract.name == reference,
+            }
+        })
+        .unique_by(|m| &m.contract)
+        .collect::<Vec<&Module>>();
+
+    match found.as_slice() {
+        [] => Ok(None),
+        [module] => Ok(Some(module)),
+        _ => Err(eyre!(
+            "`{reference}`, which is ambiguous between {}, use the `<path>:<Name>` identifier or \
+             the alias",
+            found.iter().map(|m| format!("`{}`", m.contract)).join(", ")
+        )),
+    }
+}
+
+/// Parse a selector given either as `0x` hex or as a function signature.
+pub fn parse_selector(selector: &str) -> Result<Selector> {
+    if selector.starts_with("0x") {
+        return selector
+            .parse()
+            .map_err(|e| eyre!("Invalid selector `{selector}`: {e}"));
+    }
+
+    Function::parse(selector)
+        .map(|f| f.selector())
+        .map_err(|e| eyre!("Invalid function signature `{selector}`: {e}"))
+}
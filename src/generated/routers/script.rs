@@ -0,0 +1,182 @@
+use std::{collections::HashMap, path::PathBuf};
+
+use alloy_primitives::{hex::ToHexExt, Address, B256};
+use eyre::{eyre, Result};
+use foundry_compilers::{Project, ProjectCompileOutput};
+use itertools::Itertools;
+
+use super::{
+    collect_sources, identify_sources,
+    output::relative_import,
+    pipeline::{Flavor, RouterSpec},
+    Module,
+};
+
+/// Deploys modules and the router through the CREATE2 deployer unless they already have code.
+const DEPLOY_CREATE2: &str = r#"
+    /// Deploy a module through the CREATE2 deployer unless it is already deployed, checking it
+    /// lands at the address the router delegates to.
+    function deployModule(bytes memory creationCode, address expected) internal {
+        address predicted = computeCreate2Address(SALT, keccak256(creationCode), DEPLOYER);
+        require(predicted == expected, "Module address differs from the router's, regenerate it");
+        deployCreate2(creationCode, expected);
+    }
+
+    /// Deploy the router through the CREATE2 deployer unless it is already deployed, at the
+    /// address `verify` expects without `--router-address`.
+    function deployRouter(bytes memory creationCode) internal returns (address router) {
+        router = computeCreate2Address(SALT, keccak256(creationCode), DEPLOYER);
+        deployCreate2(creationCode, router);
+    }
+
+    function deployCreate2(bytes memory creationCode, address expected) internal {
+        if (expected.code.length > 0) return;
+
+        (bool success, bytes memory deployed) = DEPLOYER.call(abi.encodePacked(SALT, creationCode));
+        require(
+            success && address(bytes20(deployed)) == expected && expected.code.length > 0,
+            "CREATE2 deployment failed"
+        );
+    }
+"#;
+
+/// Where the deployment script of a router is written, `<script>/Deploy<Router>.s.sol`.
+pub fn script_path(project: &Project, router_name: &str) -> PathBuf {
+    project
+        .paths
+        .scripts
+        .join(format!("Deploy{router_name}.s.sol"))
+}
+
+/// Render a forge script deploying the router of a spec and its modules.
+///
+/// Deterministic routers get their modules and themselves deployed through the CREATE2 deployer,
+/// skipping the ones already deployed and checking each module lands at the address baked into
+/// the router. Immutable routers get their modules deployed with `new` and passed in the `Modules`
+/// struct. Other flavors have no script, see `Flavor::has_deploy_script`.
+pub fn render_deploy_script(
+    project: &Project,
+    output: &ProjectCompileOutput,
+    spec: &RouterSpec,
+) -> Result<String> {
+    let create2 = match spec.flavor {
+        Flavor::Deterministic { deployer, salt } => Some((deployer, salt)),
+        Flavor::Immutable => None,
+        flavor => {
+            return Err(eyre!(
+                "Deployment scripts are only generated for deterministic and immutable routers, \
+                 `{}` is {flavor:?}",
+                spec.name
+            ))
+        }
+    };
+    let (deployer, salt) = create2.unzip();
+
+    let (selectors, _) = collect_sources(
+        project,
+        output,
+        spec.router.modules.clone(),
+        deployer,
+        salt,
+        spec.flavor.router_identifiers(),
+    )?;
+    let modules = selectors
+        .into_values()
+        .unique_by(|m| m.contract_identifier.clone())
+        .sorted_by(|a, b| a.contract_name.cmp(&b.contract_name))
+        .collect::<Vec<Module>>();
+
+    let script_dir = script_path(project, &spec.name)
+        .parent()
+        .map(|p| p.to_path_buf())
+        .unwrap_or_default();
+
+    // Modules are imported under the name they have in the router, their alias if any.
+    let mut sources = HashMap::new();
+    for entry in &spec.router.modules {
+        let path = identify_sources(project, vec![entry.contract.clone()])?.remove(0);
+        sources.insert(entry.name(), (entry.info().name, path));
+    }
+
+    let mut imports = vec![format!(
+        "import {{{}}} from \"{}\";",
+        spec.name,
+        relative_import(&script_dir, &spec.path(project))
+    )];
+    for module in &modules {
+        let (name, path) = sources
+            .get(&module.contract_name)
+            .ok_or_else(|| eyre!("No source found for module `{}`", module.contract_name))?;
+        let imported = match *name == module.contract_name {
+            true => name.clone(),
+            false => format!("{name} as {}", module.contract_name),
+        };
+        imports.push(format!(
+            "import {{{imported}}} from \"{}\";",
+            relative_import(&script_dir, path)
+        ));
+    }
+
+    let router_name = &spec.name;
+    let (constants, deployments, router_deployment, deploy_module) = match create2 {
+        Some((deployer, salt)) => (
+            render_constants(deployer, salt),
+            render_create2_deployments(&modules)?,
+            format!("{router_name}(payable(deployRouter(type({router_name}).creationCode)))"),
+            DEPLOY_CREATE2.to_string(),
+        ),
+        None => (
+            String::new(),
+            render_deployments(router_name, &modules),
+            format!("new {router_name}(modules)"),
+            String::new(),
+        ),
+    };
+
+    let content = include_str!("../../../assets/templates/DeployScriptTemplate.sol");
+    let content = content
+        .replace("{imports}", &imports.join("\n"))
+        .replace("{router_name}", router_name)
+        .replace("{constants}", &constants)
+        .replace("{deployments}", &deployments)
+        .replace("{router_deployment}", &router_deployment)
+        .replace("{deploy_module}", &deploy_module);
+
+    Ok(content)
+}
+
+fn render_constants(deployer: Address, salt: B256) -> String {
+    format!(
+        "    address constant DEPLOYER = {};\n    bytes32 constant SALT = {};\n",
+        deployer.to_checksum(None),
+        salt.encode_hex_with_prefix()
+    )
+}
+
+fn render_create2_deployments(modules: &[Module]) -> Result<String> {
+    modules
+        .iter()
+        .map(|m| {
+            let address = m
+                .address
+                .ok_or_else(|| eyre!("No address computed for module `{}`", m.contract_name))?;
+            Ok(format!(
+                "        deployModule(type({}).creationCode, {});",
+                m.contract_name,
+                address.to_checksum(None)
+            ))
+        })
+        .collect::<Result<Vec<String>>>()
+        .map(|lines| lines.join("\n"))
+}
+
+fn render_deployments(router_name: &str, modules: &[Module]) -> String {
+    let mut lines = vec![format!("        {router_name}.Modules memory modules;")];
+    lines.extend(modules.iter().map(|m| {
+        format!(
+            "        modules.{} = address(new {}());",
+            m.variable_name, m.contract_name
+        )
+    }));
+    lines.join("\n")
+}
@@ -0,0 +1,635 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt, fs,
+    path::PathBuf,
+};
+
+use alloy_primitives::{keccak256, B256, U256};
+use eyre::{eyre, Result};
+use foundry_compilers::{artifacts::ConfigurableContractArtifact, Project};
+use serde::{Deserialize, Serialize};
+
+/// EIP-1967 `bytes32(uint256(keccak256("eip1967.proxy.implementation")) - 1)`.
+pub const EIP1967_IMPLEMENTATION_SLOT: B256 = B256::new(alloy_primitives::hex!(
+    "360894a13ba1a3210667c828492db98dca3e2076cc3735a920a3ca505d382bbc"
+));
+
+/// EIP-1967 `bytes32(uint256(keccak256("eip1967.proxy.admin")) - 1)`.
+pub const EIP1967_ADMIN_SLOT: B256 = B256::new(alloy_primitives::hex!(
+    "b53127684a568b3173ae13b9f8a6016e243e63b6e8ee1178d6a717850b5d6103"
+));
+
+/// EIP-1967 `bytes32(uint256(keccak256("eip1967.proxy.beacon")) - 1)`.
+pub const EIP1967_BEACON_SLOT: B256 = B256::new(alloy_primitives::hex!(
+    "a3f0ad74e5423aebfd80d3ef4346578335a9a72aeaee59ff6cb3582b35133d50"
+));
+
+/// The ERC-7201 storage slot of the given namespace id,
+/// `keccak256(abi.encode(uint256(keccak256(id)) - 1)) & ~bytes32(uint256(0xff))`.
+pub fn erc7201_slot(id: &str) -> B256 {
+    let inner = U256::from_be_bytes(keccak256(id).0) - U256::from(1);
+    let mut slot = keccak256(inner.to_be_bytes::<32>());
+    slot.0[31] = 0;
+    slot
+}
+
+/// A state variable of a module and the range of slots it occupies.
+#[derive(Debug, Clone)]
+pub struct StorageSlot {
+    pub contract_name: String,
+    pub label: String,
+    pub storage_type: String,
+    /// The type as written in Solidity, stable across compilations unlike `storage_type`.
+    pub type_label: String,
+    pub slot: U256,
+    pub offset: i64,
+    pub number_of_bytes: U256,
+}
+
+impl StorageSlot {
+    /// The last slot touched by this variable (inclusive).
+    pub fn end(&self) -> U256 {
+        let bytes = self.number_of_bytes + U256::from(self.offset.max(0));
+        let slots = (bytes + U256::from(31)) / U256::from(32);
+        self.slot + slots.max(U256::from(1)) - U256::from(1)
+    }
+
+    /// Whether the variable occupies the given slot.
+    pub fn contains(&self, slot: U256) -> bool {
+        self.slot <= slot && slot <= self.end()
+    }
+
+    /// The storage bytes occupied by this variable, end excluded.
+    fn bytes(&self) -> (U256, U256) {
+        let start = self.slot * U256::from(32) + U256::from(self.offset.max(0));
+        (start, start + self.number_of_bytes.max(U256::from(1)))
+    }
+
+    /// Whether both variables share storage bytes.
+    pub fn overlaps(&self, other: &StorageSlot) -> bool {
+        let (start, end) = self.bytes();
+        let (other_start, other_end) = other.bytes();
+        start < other_end && other_start < end
+    }
+
+    /// Whether both are the same declaration, e.g. inherited from a shared base contract.
+    fn same_variable(&self, other: &StorageSlot) -> bool {
+        (&self.label, &self.type_label, self.slot, self.offset)
+            == (&other.label, &other.type_label, other.slot, other.offset)
+    }
+}
+
+/// Read the storage layout of a compiled module.
+///
+/// Requires `storageLayout` to be part of the compiler's extra output.
+pub fn storage_slots(
+    contract_name: &str,
+    artifact: &ConfigurableContractArtifact,
+) -> Result<Vec<StorageSlot>> {
+    let layout = artifact.storage_layout.as_ref().ok_or_else(|| {
+        eyre!("No storage layout found for contract `{contract_name}`, add `storageLayout` to `extra_output`")
+    })?;
+
+    layout
+        .storage
+        .iter()
+        .map(|s| {
+            let storage_type = layout.types.get(&s.storage_type);
+            let number_of_bytes = storage_type
+                .map(|t| t.number_of_bytes.as_str())
+                .unwrap_or("32");
+            let type_label =
+                storage_type.map_or_else(|| s.storage_type.clone(), |t| t.label.clone());
+
+            Ok(StorageSlot {
+                contract_name: contract_name.to_string(),
+                label: s.label.clone(),
+                storage_type: s.storage_type.clone(),
+                type_label,
+                slot: U256::from_str_radix(&s.slot, 10)?,
+                offset: s.offset,
+                number_of_bytes: U256::from_str_radix(number_of_bytes, 10)?,
+            })
+        })
+        .collect()
+}
+
+/// Two modules of a router declaring different variables in the same storage.
+#[derive(Debug, Clone)]
+pub struct StorageCollision {
+    pub first: StorageSlot,
+    pub second: StorageSlot,
+}
+
+impl fmt::Display for StorageCollision {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let describe = |v: &StorageSlot| {
+            format!(
+                "{}.{} ({}, slot {} offset {})",
+                v.contract_name, v.label, v.type_label, v.slot, v.offset
+            )
+        };
+        write!(
+            f,
+            "{} overlaps {}",
+            describe(&self.first),
+            describe(&self.second)
+        )
+    }
+}
+
+/// Find variables of different modules sharing storage.
+///
+/// Modules of a router share its storage, so a variable declared by one module overlapping a
+/// different variable of another corrupts both. Identical declarations, as inherited from a common
+/// base, are shared on purpose.
+pub fn find_storage_collisions(
+    modules: &[(String, ConfigurableContractArtifact)],
+) -> Result<Vec<StorageCollision>> {
+    let layouts = modules
+        .iter()
+        .map(|(name, artifact)| storage_slots(name, artifact))
+        .collect::<Result<Vec<Vec<StorageSlot>>>>()?;
+
+    let mut collisions = vec![];
+    for (i, first_layout) in layouts.iter().enumerate() {
+        for second_layout in &layouts[i + 1..] {
+            for first in first_layout {
+                for second in second_layout {
+                    if first.overlaps(second) && !first.same_variable(second) {
+                        collisions.push(StorageCollision {
+                            first: first.clone(),
+                            second: second.clone(),
+                        });
+                    }
+                }
+            }
+        }
+    }
+
+    Ok(collisions)
+}
+
+/// The ERC-7201 namespace ids declared with `@custom:storage-location erc7201:<id>` in the given
+/// source files.
+pub fn erc7201_namespaces<'a>(
+    sources: impl IntoIterator<Item = &'a PathBuf>,
+) -> Result<BTreeSet<String>> {
+    const ANNOTATION: &str = "@custom:storage-location erc7201:";
+
+    let mut namespaces = BTreeSet::new();
+    for source in sources {
+        let content = fs::read_to_string(source)?;
+        for (start, _) in content.match_indices(ANNOTATION) {
+            let id = content[start + ANNOTATION.len()..]
+                .split(|c: char| c.is_whitespace() || c == '*')
+                .next()
+                .unwrap_or_default();
+            if !id.is_empty() {
+                namespaces.insert(id.to_string());
+            }
+        }
+    }
+    Ok(namespaces)
+}
+
+/// Describe the ERC-7201 namespaces of the modules clashing with each other or with the router's
+/// own namespaces, given each module's namespace ids.
+///
+/// Modules sharing a namespace id share its struct on purpose, a module declaring a namespace of
+/// the router overwrites the router's state.
+fn namespace_collisions(
+    namespaces: &BTreeMap<String, BTreeSet<String>>,
+    router_namespaces: &[&str],
+) -> Vec<String> {
+    let owners =
+        router_namespaces
+            .iter()
+            .map(|id| ("the router", *id))
+            .chain(namespaces.iter().flat_map(|(module, ids)| {
+                ids.iter().map(move |id| (module.as_str(), id.as_str()))
+            }));
+
+    let mut roots: BTreeMap<B256, (&str, &str)> = BTreeMap::new();
+    let mut collisions = vec![];
+    for (owner, id) in owners {
+        let root = erc7201_slot(id);
+        match roots.get(&root) {
+            None => {
+                roots.insert(root, (owner, id));
+            }
+            Some((first, first_id)) if *first_id != id => collisions.push(format!(
+                "ERC-7201 namespace `{id}` of {owner} has the root of `{first_id}` of {first} ({root})"
+            )),
+            Some((first, _)) if *first == "the router" => collisions.push(format!(
+                "{owner} declares the router's ERC-7201 namespace `{id}` ({root})"
+            )),
+            Some(_) => {}
+        }
+    }
+    collisions
+}
+
+/// Fail if modules of a router declare colliding variables, or ERC-7201 namespaces clashing with
+/// another module's or the router's own namespaces.
+///
+/// Namespaced storage lives at hashed slots outside of the compiler's layout, so namespaces are
+/// compared by their roots.
+pub fn check_storage_collisions(
+    modules: &[(String, ConfigurableContractArtifact)],
+    namespaces: &BTreeMap<String, BTreeSet<String>>,
+    router_namespaces: &[&str],
+) -> Result<()> {
+    let mut collisions = find_storage_collisions(modules)?
+        .iter()
+        .map(|c| c.to_string())
+        .collect::<Vec<String>>();
+    collisions.extend(namespace_collisions(namespaces, router_namespaces));
+
+    if !collisions.is_empty() {
+        return Err(eyre!(
+            "Storage collisions between modules:\n  {}",
+            collisions.join("\n  ")
+        ));
+    }
+
+    Ok(())
+}
+
+/// A state variable recorded in a storage snapshot.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SnapshotVariable {
+    pub label: String,
+    #[serde(rename = "type")]
+    pub type_label: String,
+    pub slot: U256,
+    pub offset: i64,
+}
+
+/// The state variables of each module by contract name, as saved between generations.
+pub type StorageSnapshot = BTreeMap<String, Vec<SnapshotVariable>>;
+
+/// Where the storage snapshot of a router is saved, `{name}` is replaced by the router name.
+pub fn snapshot_path(project: &Project, snapshot: &str, router_name: &str) -> PathBuf {
+    project.root().join(snapshot.replace("{name}", router_name))
+}
+
+/// Record the storage layout of the given modules.
+pub fn storage_snapshot(
+    modules: &[(String, ConfigurableContractArtifact)],
+) -> Result<StorageSnapshot> {
+    modules
+        .iter()
+        .map(|(module_name, artifact)| {
+            let variables = storage_slots(module_name, artifact)?
+                .into_iter()
+                .map(|s| SnapshotVariable {
+                    label: s.label,
+                    type_label: s.type_label,
+                    slot: s.slot,
+                    offset: s.offset,
+                })
+                .collect();
+            Ok((module_name.clone(), variables))
+        })
+        .collect()
+}
+
+/// A change to a module's storage layout that corrupts the state of live routers.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "change", rename_all = "snake_case")]
+pub enum LayoutChange {
+    /// The variable no longer exists, its slot may be reused by another one.
+    Removed {
+        module: String,
+        variable: SnapshotVariable,
+    },
+    /// The variable is stored at the same place with another type.
+    Retyped {
+        module: String,
+        label: String,
+        from: String,
+        to: String,
+    },
+    /// The variable moved to another slot or offset.
+    Shifted {
+        module: String,
+        label: String,
+        from: (U256, i64),
+        to: (U256, i64),
+    },
+}
+
+impl fmt::Display for LayoutChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Removed { module, variable } => write!(
+                f,
+                "{module}.{} ({}) removed from slot {}",
+                variable.label, variable.type_label, variable.slot
+            ),
+            Self::Retyped {
+                module,
+                label,
+                from,
+                to,
+            } => write!(f, "{module}.{label} changed type from {from} to {to}"),
+            Self::Shifted {
+                module,
+                label,
+                from,
+                to,
+            } => write!(
+                f,
+                "{module}.{label} moved from slot {} offset {} to slot {} offset {}",
+                from.0, from.1, to.0, to.1
+            ),
+        }
+    }
+}
+
+/// Compare module layouts with a previous snapshot.
+///
+/// Only modules present in both are compared. Appending variables is compatible, removing,
+/// retyping or moving existing ones is not.
+pub fn compare_layouts(previous: &StorageSnapshot, current: &StorageSnapshot) -> Vec<LayoutChange> {
+    let mut changes = vec![];
+
+    for (module, variables) in current {
+        let Some(previous_variables) = previous.get(module) else {
+            continue;
+        };
+
+        for old in previous_variables {
+            let Some(new) = variables.iter().find(|v| v.label == old.label) else {
+                changes.push(LayoutChange::Removed {
+                    module: module.clone(),
+                    variable: old.clone(),
+                });
+                continue;
+            };
+
+            if new.type_label != old.type_label {
+                changes.push(LayoutChange::Retyped {
+                    module: module.clone(),
+                    label: old.label.clone(),
+                    from: old.type_label.clone(),
+                    to: new.type_label.clone(),
+                });
+            }
+            if (new.slot, new.offset) != (old.slot, old.offset) {
+                changes.push(LayoutChange::Shifted {
+                    module: module.clone(),
+                    label: old.label.clone(),
+                    from: (old.slot, old.offset),
+                    to: (new.slot, new.offset),
+                });
+            }
+        }
+    }
+
+    changes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn variable(label: &str, type_label: &str, slot: u64, offset: i64) -> SnapshotVariable {
+        SnapshotVariable {
+            label: label.to_string(),
+            type_label: type_label.to_string(),
+            slot: U256::from(slot),
+            offset,
+        }
+    }
+
+    fn snapshot(variables: Vec<SnapshotVariable>) -> StorageSnapshot {
+        StorageSnapshot::from([("Counter".to_string(), variables)])
+    }
+
+    #[test]
+    fn appending_variables_is_compatible() {
+        let previous = snapshot(vec![variable("count", "uint256", 0, 0)]);
+        let current = snapshot(vec![
+            variable("count", "uint256", 0, 0),
+            variable("owner", "address", 1, 0),
+        ]);
+
+        assert!(compare_layouts(&previous, &current).is_empty());
+    }
+
+    #[test]
+    fn detects_removed_retyped_and_shifted_variables() {
+        let previous = snapshot(vec![
+            variable("count", "uint256", 0, 0),
+            variable("owner", "address", 1, 0),
+            variable("paused", "bool", 1, 20),
+        ]);
+        let current = snapshot(vec![
+            variable("count", "uint128", 0, 0),
+            variable("paused", "bool", 2, 0),
+        ]);
+
+        let changes = compare_layouts(&previous, &current);
+        assert_eq!(changes.len(), 3);
+        assert!(matches!(
+            &changes[0],
+            LayoutChange::Retyped { label, from, to, .. }
+                if label == "count" && from == "uint256" && to == "uint128"
+        ));
+        assert!(matches!(
+            &changes[1],
+            LayoutChange::Removed { variable, .. } if variable.label == "owner"
+        ));
+        assert!(matches!(
+            &changes[2],
+            LayoutChange::Shifted { label, from, to, .. }
+                if label == "paused" && *from == (U256::from(1), 20) && *to == (U256::from(2), 0)
+        ));
+    }
+
+    #[test]
+    fn ignores_modules_missing_from_either_snapshot() {
+        let previous = snapshot(vec![variable("count", "uint256", 0, 0)]);
+        let current = StorageSnapshot::from([(
+            "Other".to_string(),
+            vec![variable("count", "address", 3, 0)],
+        )]);
+
+        assert!(compare_layouts(&previous, &current).is_empty());
+        assert!(compare_layouts(&current, &previous).is_empty());
+    }
+
+    #[test]
+    fn snapshot_roundtrip() {
+        let previous = snapshot(vec![variable("count", "uint256", 0, 0)]);
+        let json = serde_json::to_string(&previous).unwrap();
+
+        assert!(json.contains(r#""type":"uint256""#));
+        assert_eq!(
+            serde_json::from_str::<StorageSnapshot>(&json).unwrap(),
+            previous
+        );
+    }
+
+    fn slot(label: &str, slot: u64, offset: i64, number_of_bytes: u64) -> StorageSlot {
+        StorageSlot {
+            contract_name: "Counter".to_string(),
+            label: label.to_string(),
+            storage_type: "t_uint256".to_string(),
+            type_label: "uint256".to_string(),
+            slot: U256::from(slot),
+            offset,
+            number_of_bytes: U256::from(number_of_bytes),
+        }
+    }
+
+    /// A compiled module declaring `(label, type, slot, offset, number of bytes)` variables.
+    fn module(
+        name: &str,
+        variables: &[(&str, &str, u64, i64, u64)],
+    ) -> (String, ConfigurableContractArtifact) {
+        let storage = variables
+            .iter()
+            .map(|(label, type_label, slot, offset, _)| {
+                serde_json::json!({
+                    "astId": 1,
+                    "contract": format!("src/{name}.sol:{name}"),
+                    "label": label,
+                    "offset": offset,
+                    "slot": slot.to_string(),
+                    "type": format!("t_{type_label}"),
+                })
+            })
+            .collect::<Vec<_>>();
+        let types = variables
+            .iter()
+            .map(|(_, type_label, _, _, number_of_bytes)| {
+                (
+                    format!("t_{type_label}"),
+                    serde_json::json!({
+                        "encoding": "inplace",
+                        "label": type_label,
+                        "numberOfBytes": number_of_bytes.to_string(),
+                    }),
+                )
+            })
+            .collect::<serde_json::Map<_, _>>();
+        let layout = serde_json::json!({ "storage": storage, "types": types });
+
+        let artifact = ConfigurableContractArtifact {
+            storage_layout: Some(serde_json::from_value(layout).unwrap()),
+            ..Default::default()
+        };
+        (name.to_string(), artifact)
+    }
+
+    #[test]
+    fn end_covers_multi_slot_variables() {
+        assert_eq!(slot("count", 3, 0, 32).end(), U256::from(3));
+        assert_eq!(slot("pair", 3, 0, 64).end(), U256::from(4));
+        assert_eq!(slot("flag", 3, 31, 1).end(), U256::from(3));
+        assert!(slot("pair", 3, 0, 64).contains(U256::from(4)));
+        assert!(!slot("pair", 3, 0, 64).contains(U256::from(5)));
+    }
+
+    #[test]
+    fn packed_variables_overlap_only_on_shared_bytes() {
+        let low = slot("low", 0, 0, 16);
+        let high = slot("high", 0, 16, 16);
+        let word = slot("word", 0, 0, 32);
+        let next = slot("next", 1, 0, 32);
+
+        assert!(!low.overlaps(&high));
+        assert!(!high.overlaps(&low));
+        assert!(word.overlaps(&low));
+        assert!(word.overlaps(&high));
+        assert!(!word.overlaps(&next));
+        assert!(slot("pair", 0, 0, 64).overlaps(&next));
+    }
+
+    #[test]
+    fn finds_collisions_between_modules() {
+        let modules = [
+            module(
+                "First",
+                &[
+                    ("owner", "address", 0, 0, 20),
+                    ("count", "uint256", 1, 0, 32),
+                ],
+            ),
+            module(
+                "Second",
+                &[
+                    ("owner", "address", 0, 0, 20),
+                    ("total", "uint128", 1, 16, 16),
+                ],
+            ),
+        ];
+
+        let collisions = find_storage_collisions(&modules).unwrap();
+        assert_eq!(collisions.len(), 1);
+        assert_eq!(collisions[0].first.label, "count");
+        assert_eq!(collisions[0].second.label, "total");
+    }
+
+    #[test]
+    fn erc7201_slot_matches_the_spec() {
+        assert_eq!(
+            erc7201_slot("example.main"),
+            B256::new(alloy_primitives::hex!(
+                "183a6125c38840424c4a85fa12bab2ab606c4b6d0e7cc73c0c06ba5300eab500"
+            ))
+        );
+    }
+
+    #[test]
+    fn reads_erc7201_namespaces() {
+        let path = std::env::temp_dir().join("cannon-rs-erc7201-namespaces.sol");
+        fs::write(
+            &path,
+            "/// @custom:storage-location erc7201:example.main\n\
+             struct MainStorage { uint256 x; }\n\
+             /** @custom:storage-location erc7201:example.other*/\n\
+             struct OtherStorage { uint256 y; }\n",
+        )
+        .unwrap();
+
+        let namespaces = erc7201_namespaces([&path]).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(
+            namespaces.into_iter().collect::<Vec<_>>(),
+            ["example.main", "example.other"]
+        );
+    }
+
+    #[test]
+    fn reports_modules_using_the_routers_namespaces() {
+        let modules = [module("First", &[("count", "uint256", 0, 0, 32)])];
+        let namespaces = |ids: &[(&str, &str)]| {
+            let mut namespaces = BTreeMap::<String, BTreeSet<String>>::new();
+            for (module, id) in ids {
+                namespaces
+                    .entry(module.to_string())
+                    .or_default()
+                    .insert(id.to_string());
+            }
+            namespaces
+        };
+        let router = ["cannon.router.features"];
+
+        let shared = namespaces(&[("First", "example.main"), ("Second", "example.main")]);
+        assert!(check_storage_collisions(&modules, &shared, &router).is_ok());
+
+        let clashing = namespaces(&[
+            ("First", "example.main"),
+            ("Second", "cannon.router.features"),
+        ]);
+        let err = check_storage_collisions(&modules, &clashing, &router).unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("Second declares the router's ERC-7201 namespace `cannon.router.features`"));
+        assert!(check_storage_collisions(&modules, &clashing, &[]).is_ok());
+    }
+}
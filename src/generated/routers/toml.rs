@@ -1,10 +1,72 @@
 use eyre::Result;
+use itertools::Itertools;
 use serde::Deserialize;
 use std::{collections::HashMap, fs, path::PathBuf};
 
-#[derive(Debug, Deserialize)]
+use super::{
+    lint::LintOptions, module_contracts, proxy::ProxyKind, unknown::UnknownSelector,
+    DispatchOptions, ModuleEntry,
+};
+
+#[derive(Debug, Clone, Deserialize)]
 pub struct Router {
-    pub modules: Vec<String>,
+    /// Module identifiers, or `{ contract = "<path>:<Name>", alias = "<Alias>" }` tables naming
+    /// the module after the alias in generated code.
+    pub modules: Vec<ModuleEntry>,
+    /// Cache registry lookups in transient storage (registry routers only).
+    #[serde(default)]
+    pub cache: bool,
+    /// Behavior for selectors not implemented by any module.
+    #[serde(default)]
+    pub unknown_selector: UnknownSelector,
+    /// Output directory or file path relative to the project root, `{name}` is replaced by the
+    /// router name. Defaults to `<src>/generated/routers`.
+    pub output: Option<String>,
+    /// Storage layout snapshot of the modules relative to the project root, `{name}` is replaced
+    /// by the router name. Layout changes breaking the previous snapshot fail generation.
+    pub storage_snapshot: Option<String>,
+    /// The proxy the router is deployed behind, whose functions must not be routed.
+    pub proxy: Option<ProxyKind>,
+    /// Function signatures or `0x` selectors the router must not route.
+    #[serde(default)]
+    pub reserved_selectors: Vec<String>,
+    /// Also generate a forge script deploying the modules and the router (deterministic and
+    /// immutable routers only, others are skipped with a warning).
+    #[serde(default)]
+    pub deploy_script: bool,
+    /// Severity of the delegatecall-safety rules checked on modules.
+    #[serde(default)]
+    pub lint: LintOptions,
+    /// Hooks and guards run before dispatching.
+    #[serde(flatten)]
+    pub dispatch: DispatchOptions,
+}
+
+impl Router {
+    /// A router of the given modules without aliases or dispatch options.
+    pub fn new(modules: Vec<String>, unknown_selector: UnknownSelector) -> Self {
+        Self {
+            modules: modules.into_iter().map(ModuleEntry::from).collect(),
+            cache: false,
+            unknown_selector,
+            output: None,
+            storage_snapshot: None,
+            proxy: None,
+            reserved_selectors: vec![],
+            deploy_script: false,
+            lint: LintOptions::default(),
+            dispatch: DispatchOptions::default(),
+        }
+    }
+
+    /// The contracts compiled for this router, its modules and hook libraries.
+    pub fn contracts(&self) -> Result<Vec<String>> {
+        let mut contracts = module_contracts(&self.modules);
+        for hook in &self.dispatch.hooks {
+            contracts.push(hook.library()?);
+        }
+        Ok(contracts)
+    }
 }
 
 #[derive(Debug, Deserialize)]
@@ -19,4 +81,12 @@ impl TomlDefintion {
         let toml: TomlDefintion = toml::from_str(&content)?;
         Ok(toml)
     }
+
+    /// The routers sorted by name.
+    pub fn sorted_routers(self) -> Vec<(String, Router)> {
+        self.routers
+            .into_iter()
+            .sorted_by(|(a, _), (b, _)| a.cmp(b))
+            .collect()
+    }
 }
@@ -0,0 +1,346 @@
+use std::str::FromStr;
+
+use alloy_json_abi::{Error, JsonAbi};
+use alloy_primitives::{hex::ToHexExt, Address};
+use eyre::{eyre, Result};
+use serde::{Deserialize, Serialize};
+
+use super::{scope::find_module, Module};
+
+/// What the generated router does with a selector that no module implements.
+///
+/// In TOML this is either `unknown_selector = "revert"` or a single-key table such as
+/// `unknown_selector = { error = "NotImplemented(bytes4 sel)" }`,
+/// `unknown_selector = { module = "src/Fallback.sol:FallbackModule" }` or
+/// `unknown_selector = { router = "0x..." }`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum UnknownSelector {
+    /// Revert with a custom error taking either no arguments or the `bytes4` selector.
+    Error(String),
+    /// Revert without any return data.
+    Revert,
+    /// Forward the call to one of the router's modules, given as its `<path>:<Name>` identifier,
+    /// alias or unambiguous contract name.
+    Module(String),
+    /// Forward the call to another router.
+    Router(Address),
+}
+
+impl Default for UnknownSelector {
+    fn default() -> Self {
+        Self::Error("UnknownSelector(bytes4 sel)".to_string())
+    }
+}
+
+impl FromStr for UnknownSelector {
+    type Err = eyre::Error;
+
+    /// Parse the CLI form: `revert`, `error:<SIGNATURE>`, `module:<MODULE>` or `router:<ADDRESS>`.
+    fn from_str(s: &str) -> Result<Self> {
+        match s.split_once(':') {
+            None if s == "revert" => Ok(Self::Revert),
+            Some(("error", signature)) => Ok(Self::Error(signature.to_string())),
+            Some(("module", module)) => Ok(Self::Module(module.to_string())),
+            Some(("router", address)) => Ok(Self::Router(address.parse()?)),
+            _ => Err(eyre!(
+                "Invalid unknown selector behavior `{s}`, expected `revert`, `error:<SIGNATURE>`, `module:<MODULE>` or `router:<ADDRESS>`"
+            )),
+        }
+    }
+}
+
+impl UnknownSelector {
+    /// The custom error raised for unknown selectors, if any.
+    pub fn error(&self) -> Result<Option<Error>> {
+        let Self::Error(signature) = self else {
+            return Ok(None);
+        };
+
+        let signature = signature
+            .trim()
+            .trim_start_matches("error ")
+            .trim_end_matches(';');
+        let error = Error::parse(signature)
+            .map_err(|e| eyre!("Invalid unknown selector error `{signature}`: {e}"))?;
+
+        match error.inputs.as_slice() {
+            [] => {}
+            [input] if input.ty == "bytes4" => {}
+            _ => {
+                return Err(eyre!(
+                    "Unknown selector error `{}` must take no arguments or a single `bytes4`",
+                    error.signature()
+                ))
+            }
+        }
+
+        Ok(Some(error))
+    }
+
+    /// Add the custom error, if any, to the router's interface.
+    pub fn extend_abi(&self, abi: &JsonAbi) -> Result<JsonAbi> {
+        let mut abi = abi.clone();
+        if let Some(error) = self.error()? {
+            abi.errors
+                .entry(error.name.clone())
+                .or_default()
+                .push(error);
+        }
+        Ok(abi)
+    }
+
+    /// Render the error declaration for the router contract.
+    pub fn render_declaration(&self) -> Result<String> {
+        let Some(error) = self.error()? else {
+            return Ok(String::new());
+        };
+
+        let params = error
+            .inputs
+            .iter()
+            .map(|input| format!("{} {}", input.ty, input.name).trim().to_string())
+            .collect::<Vec<String>>()
+            .join(", ");
+
+        Ok(format!("    error {}({});\n", error.name, params))
+    }
+
+    /// Render the statements executed when no module matches the selector.
+    ///
+    /// `module_address` renders the expression resolving a module's address, and `forward`
+    /// renders the assignment of an address expression to the template's delegatecall target.
+    pub fn render_fallback<F, K>(
+        &self,
+        modules: &Vec<Module>,
+        module_address: K,
+        forward: F,
+    ) -> Result<String>
+    where
+        F: Fn(&str) -> String,
+        K: Fn(&Module) -> String,
+    {
+        let statement = match self {
+            Self::Error(_) => {
+                let error = self.error()?.unwrap();
+                if error.inputs.is_empty() {
+                    format!("revert {}();", error.name)
+                } else {
+                    format!("revert {}(sig4);", error.name)
+                }
+            }
+            Self::Revert => "revert();".to_string(),
+            Self::Module(name) => {
+                let module = find_module(modules, name)
+                    .map_err(|e| eyre!("Unknown selector module is {e}"))?
+                    .ok_or_else(|| {
+                        eyre!("Unknown selector module `{name}` is not one of the router's modules")
+                    })?;
+                forward(&module_address(module))
+            }
+            Self::Router(address) => forward(&address.to_checksum(None)),
+        };
+
+        Ok(format!("            {statement}"))
+    }
+
+    /// Render the Yul statements of a standalone Yul router executed when no module matches the
+    /// selector. Forwarding assigns the target to `implementation`.
+    pub fn render_yul_fallback(&self, modules: &Vec<Module>) -> Result<String> {
+        let statements = match self {
+            Self::Error(_) => {
+                let error = self.error()?.unwrap();
+                let mut statements = vec![format!(
+                    "mstore(0, shl(224, {})) // {}",
+                    error.selector().encode_hex_with_prefix(),
+                    error.signature()
+                )];
+                if error.inputs.is_empty() {
+                    statements.push("revert(0, 0x04)".to_string());
+                } else {
+                    statements.push("mstore(4, shl(224, shr(224, calldataload(0))))".to_string());
+                    statements.push("revert(0, 0x24)".to_string());
+                }
+                statements
+            }
+            Self::Revert => vec!["revert(0, 0)".to_string()],
+            Self::Module(name) => {
+                let module = find_module(modules, name)
+                    .map_err(|e| eyre!("Unknown selector module is {e}"))?
+                    .ok_or_else(|| {
+                        eyre!("Unknown selector module `{name}` is not one of the router's modules")
+                    })?;
+                let address = module
+                    .address
+                    .ok_or_else(|| eyre!("No address computed for module `{name}`"))?;
+                vec![format!(
+                    "implementation := {}",
+                    address.encode_hex_with_prefix()
+                )]
+            }
+            Self::Router(address) => {
+                vec![format!(
+                    "implementation := {}",
+                    address.encode_hex_with_prefix()
+                )]
+            }
+        };
+
+        Ok(statements
+            .iter()
+            .map(|s| format!("                {s}"))
+            .collect::<Vec<String>>()
+            .join("\n"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::tests::module;
+    use super::*;
+
+    fn modules() -> Vec<Module> {
+        vec![module("src/Fallback.sol:Fallback", "fallbackCall()")]
+    }
+
+    fn render(unknown_selector: &UnknownSelector) -> Result<String> {
+        unknown_selector.render_fallback(
+            &modules(),
+            |m| m.contract_name.to_uppercase(),
+            |target| format!("implementation = {target};"),
+        )
+    }
+
+    #[test]
+    fn parses_cli_behaviors() {
+        let parse = |s: &str| s.parse::<UnknownSelector>();
+        let router = Address::repeat_byte(0x11);
+
+        assert_eq!(parse("revert").unwrap(), UnknownSelector::Revert);
+        assert_eq!(
+            parse("error:NotImplemented()").unwrap(),
+            UnknownSelector::Error("NotImplemented()".to_string())
+        );
+        assert_eq!(
+            parse("module:src/Fallback.sol:Fallback").unwrap(),
+            UnknownSelector::Module("src/Fallback.sol:Fallback".to_string())
+        );
+        assert_eq!(
+            parse(&format!("router:{router}")).unwrap(),
+            UnknownSelector::Router(router)
+        );
+        assert!(parse("forward").is_err());
+        assert!(parse("router:0x1234").is_err());
+    }
+
+    #[test]
+    fn parses_toml_behaviors() {
+        #[derive(Deserialize)]
+        struct Router {
+            unknown_selector: UnknownSelector,
+        }
+        let parse = |s: &str| toml::from_str::<Router>(s).unwrap().unknown_selector;
+
+        assert_eq!(
+            parse(r#"unknown_selector = "revert""#),
+            UnknownSelector::Revert
+        );
+        assert_eq!(
+            parse(r#"unknown_selector = { error = "NotImplemented(bytes4 sel)" }"#),
+            UnknownSelector::Error("NotImplemented(bytes4 sel)".to_string())
+        );
+        assert_eq!(
+            parse(r#"unknown_selector = { module = "Fallback" }"#),
+            UnknownSelector::Module("Fallback".to_string())
+        );
+    }
+
+    #[test]
+    fn errors_take_nothing_or_the_selector() {
+        let error = |signature: &str| UnknownSelector::Error(signature.to_string()).error();
+
+        assert_eq!(
+            error("NotImplemented()").unwrap().unwrap().name,
+            "NotImplemented"
+        );
+        assert!(error("error NotImplemented(bytes4 sel);")
+            .unwrap()
+            .is_some());
+        assert!(error("NotImplemented(uint256 value)").is_err());
+        assert!(error("NotImplemented(bytes4 a, bytes4 b)").is_err());
+        assert!(error("not an error").is_err());
+        assert!(UnknownSelector::Revert.error().unwrap().is_none());
+    }
+
+    #[test]
+    fn renders_the_error_declaration() {
+        assert_eq!(
+            UnknownSelector::default().render_declaration().unwrap(),
+            "    error UnknownSelector(bytes4 sel);\n"
+        );
+        assert_eq!(
+            UnknownSelector::Error("NotImplemented()".to_string())
+                .render_declaration()
+                .unwrap(),
+            "    error NotImplemented();\n"
+        );
+        assert_eq!(UnknownSelector::Revert.render_declaration().unwrap(), "");
+    }
+
+    #[test]
+    fn renders_each_fallback() {
+        let router = Address::repeat_byte(0x11);
+
+        assert_eq!(
+            render(&UnknownSelector::default()).unwrap(),
+            "            revert UnknownSelector(sig4);"
+        );
+        assert_eq!(
+            render(&UnknownSelector::Error("NotImplemented()".to_string())).unwrap(),
+            "            revert NotImplemented();"
+        );
+        assert_eq!(
+            render(&UnknownSelector::Revert).unwrap(),
+            "            revert();"
+        );
+        assert_eq!(
+            render(&UnknownSelector::Module("Fallback".to_string())).unwrap(),
+            "            implementation = FALLBACK;"
+        );
+        assert_eq!(
+            render(&UnknownSelector::Router(router)).unwrap(),
+            format!("            implementation = {};", router.to_checksum(None))
+        );
+        assert!(render(&UnknownSelector::Module("Missing".to_string())).is_err());
+    }
+
+    #[test]
+    fn renders_each_yul_fallback() {
+        let modules = modules();
+        let fallback = modules[0].address.unwrap().encode_hex_with_prefix();
+
+        let default = UnknownSelector::default()
+            .render_yul_fallback(&modules)
+            .unwrap();
+        assert!(default.contains("mstore(4, shl(224, shr(224, calldataload(0))))"));
+        assert!(default.ends_with("revert(0, 0x24)"));
+
+        let error = UnknownSelector::Error("NotImplemented()".to_string());
+        assert!(error
+            .render_yul_fallback(&modules)
+            .unwrap()
+            .ends_with("revert(0, 0x04)"));
+        assert_eq!(
+            UnknownSelector::Revert
+                .render_yul_fallback(&modules)
+                .unwrap(),
+            "                revert(0, 0)"
+        );
+        assert_eq!(
+            UnknownSelector::Module("Fallback".to_string())
+                .render_yul_fallback(&modules)
+                .unwrap(),
+            format!("                implementation := {fallback}")
+        );
+    }
+}
@@ -0,0 +1,115 @@
+use alloy_json_abi::{Function, JsonAbi};
+use alloy_primitives::{hex::ToHexExt, Address, B256};
+use eyre::{eyre, Result};
+use foundry_compilers::{Project, ProjectCompileOutput};
+
+use super::{
+    lint::check_slot_writes,
+    render_modules_with_template,
+    storage::{EIP1967_ADMIN_SLOT, EIP1967_BEACON_SLOT, EIP1967_IMPLEMENTATION_SLOT},
+    unknown::UnknownSelector,
+    DispatchOptions, Module, ModuleEntry,
+};
+
+/// Functions implemented by the router itself to support UUPS upgrades.
+const UPGRADE_FUNCTIONS: [&str; 3] = [
+    "function proxiableUUID() external view returns (bytes32)",
+    "function changeAdmin(address newAdmin) external",
+    "function upgradeToAndCall(address newImplementation, bytes data) external payable",
+];
+
+/// Identifiers the router declares next to the module constants, the proxy slots included.
+pub const ROUTER_IDENTIFIERS: &[&str] = &["FEATURES_SLOT", "IMPLEMENTATION_SLOT", "ADMIN_SLOT"];
+
+pub fn generate_router(
+    project: &Project,
+    output: &ProjectCompileOutput,
+    router_name: String,
+    module_names: Vec<ModuleEntry>,
+    deployer: Address,
+    salt: B256,
+    unknown_selector: &UnknownSelector,
+    dispatch: &DispatchOptions,
+) -> Result<String> {
+    // The proxy's slots are outside of the compiler's layout, only explicit writes can clash.
+    check_slot_writes(
+        project,
+        output,
+        module_names.clone(),
+        &[
+            ("EIP-1967 implementation slot", EIP1967_IMPLEMENTATION_SLOT),
+            ("EIP-1967 admin slot", EIP1967_ADMIN_SLOT),
+            ("EIP-1967 beacon slot", EIP1967_BEACON_SLOT),
+        ],
+    )?;
+
+    super::generate_router(
+        project,
+        output,
+        router_name,
+        module_names,
+        Some(deployer),
+        Some(salt),
+        ROUTER_IDENTIFIERS,
+        dispatch,
+        &|m: &Module| format!("result := {}", m.constant_name),
+        &|router_name: &String, modules: &Vec<Module>, abi: &JsonAbi| {
+            template(router_name, modules, abi, unknown_selector)
+        },
+    )
+}
+
+fn template(
+    router_name: &String,
+    modules: &Vec<Module>,
+    abi: &JsonAbi,
+    unknown_selector: &UnknownSelector,
+) -> Result<String> {
+    let mut abi = unknown_selector.extend_abi(abi)?;
+    for signature in UPGRADE_FUNCTIONS {
+        let function = Function::parse(signature)?;
+        if abi.functions().any(|f| f.selector() == function.selector()) {
+            return Err(eyre!(
+                "Selector `{}` is reserved by the upgradeable router",
+                function.signature()
+            ));
+        }
+        abi.functions
+            .entry(function.name.clone())
+            .or_default()
+            .push(function);
+    }
+
+    let interface = abi.to_sol(format!("I{router_name}").as_str(), None);
+
+    let module_lookup = render_modules_with_template(modules, &|m| {
+        format!(
+            "    address constant {} = {};",
+            m.constant_name,
+            &m.address.unwrap().to_checksum(None)
+        )
+    });
+
+    let unknown_selector_error = unknown_selector.render_declaration()?;
+    let unknown_selector_fallback = unknown_selector.render_fallback(
+        modules,
+        |m| m.constant_name.clone(),
+        |target| format!("implementation = {target};"),
+    )?;
+
+    // Create the router file content.
+    let router_content = include_str!("../../../assets/templates/UpgradeableRouterTemplate.sol");
+    let router_content = router_content
+        .replace("{interface}", &interface)
+        .replace("{router_name}", &router_name)
+        .replace("{modules}", &module_lookup)
+        .replace("{unknown_selector_error}\n", &unknown_selector_error)
+        .replace("{unknown_selector}", &unknown_selector_fallback)
+        .replace(
+            "{implementation_slot}",
+            &EIP1967_IMPLEMENTATION_SLOT.encode_hex_with_prefix(),
+        )
+        .replace("{admin_slot}", &EIP1967_ADMIN_SLOT.encode_hex_with_prefix());
+
+    Ok(router_content)
+}
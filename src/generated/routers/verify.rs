@@ -0,0 +1,245 @@
+use std::path::Path;
+
+use alloy_primitives::{Address, Bytes, B256};
+use eyre::{eyre, Result};
+use foundry_compilers::{artifacts::ConfigurableContractArtifact, Project, ProjectCompileOutput};
+use serde::Serialize;
+
+use super::{find_artifacts, ModuleEntry};
+
+/// Outcome of comparing a deployment with its artifact.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Status {
+    /// The on-chain code matches the artifact.
+    Verified,
+    /// No code at the expected address.
+    Missing,
+    /// Code is deployed but differs from the artifact.
+    Mismatch,
+}
+
+/// The runtime code a contract is expected to have at a given address.
+#[derive(Debug, Clone)]
+pub struct ExpectedCode {
+    pub name: String,
+    pub address: Address,
+    pub code: Bytes,
+    /// `(start, length)` ranges filled with immutable values at deployment.
+    pub immutables: Vec<(usize, usize)>,
+}
+
+/// Result of verifying one contract.
+#[derive(Debug, Clone, Serialize)]
+pub struct Deployment {
+    pub name: String,
+    pub address: Address,
+    pub status: Status,
+}
+
+/// The expected code of every module, at its CREATE2 address from `deployer` and `salt`.
+pub fn expected_modules(
+    project: &Project,
+    output: &ProjectCompileOutput,
+    module_names: Vec<ModuleEntry>,
+    deployer: Address,
+    salt: B256,
+) -> Result<Vec<ExpectedCode>> {
+    find_artifacts(project, output, module_names)?
+        .into_iter()
+        .map(|(name, artifact)| {
+            let creation = artifact
+                .bytecode
+                .as_ref()
+                .and_then(|b| b.bytes())
+                .ok_or_else(|| eyre!("No bytecode found for contract `{name}`"))?;
+            let address = Address::create2_from_code(&deployer, &salt, creation);
+            expected_code(name, address, &artifact)
+        })
+        .collect()
+}
+
+/// The expected code of a generated router at the given address, or its CREATE2 address from
+/// `deployer` and `salt`.
+pub fn expected_router(
+    project: &Project,
+    output: &ProjectCompileOutput,
+    router_name: &str,
+    router_path: &Path,
+    address: Option<Address>,
+    deployer: Address,
+    salt: B256,
+) -> Result<ExpectedCode> {
+    let path = router_path
+        .strip_prefix(project.root())
+        .unwrap_or(router_path);
+    let identifier = format!("{}:{router_name}", path.to_string_lossy());
+    let (name, artifact) = find_artifacts(project, output, vec![ModuleEntry::new(identifier)])?
+        .into_iter()
+        .next()
+        .ok_or_else(|| eyre!("Router `{router_name}` not found"))?;
+
+    let address = match address {
+        Some(address) => address,
+        None => {
+            let creation = artifact
+                .bytecode
+                .as_ref()
+                .and_then(|b| b.bytes())
+                .ok_or_else(|| eyre!("No bytecode found for router `{router_name}`"))?;
+            Address::create2_from_code(&deployer, &salt, creation)
+        }
+    };
+
+    expected_code(name, address, &artifact)
+}
+
+fn expected_code(
+    name: String,
+    address: Address,
+    artifact: &ConfigurableContractArtifact,
+) -> Result<ExpectedCode> {
+    let deployed = artifact
+        .deployed_bytecode
+        .as_ref()
+        .ok_or_else(|| eyre!("No deployed bytecode found for contract `{name}`"))?;
+
+    let code = deployed
+        .bytecode
+        .as_ref()
+        .and_then(|b| b.bytes())
+        .cloned()
+        .ok_or_else(|| eyre!("Deployed bytecode of `{name}` needs linking"))?;
+
+    let immutables = deployed
+        .immutable_references
+        .values()
+        .flatten()
+        .map(|offsets| (offsets.start as usize, offsets.length as usize))
+        .collect();
+
+    Ok(ExpectedCode {
+        name,
+        address,
+        code,
+        immutables,
+    })
+}
+
+/// Compare deployed code with the expected code, ignoring immutable values and, optionally,
+/// the trailing CBOR metadata.
+pub fn compare(expected: &ExpectedCode, actual: &[u8], ignore_metadata: bool) -> Status {
+    if actual.is_empty() {
+        return Status::Missing;
+    }
+
+    let (mut expected_code, mut actual_code) = (expected.code.to_vec(), actual.to_vec());
+    if ignore_metadata {
+        expected_code.truncate(strip_metadata(&expected_code).len());
+        actual_code.truncate(strip_metadata(&actual_code).len());
+    }
+
+    if expected_code.len() != actual_code.len() {
+        return Status::Mismatch;
+    }
+
+    for &(start, length) in &expected.immutables {
+        let end = (start + length).min(expected_code.len());
+        if start < end {
+            expected_code[start..end].fill(0);
+            actual_code[start..end].fill(0);
+        }
+    }
+
+    match expected_code == actual_code {
+        true => Status::Verified,
+        false => Status::Mismatch,
+    }
+}
+
+/// Runtime code without the CBOR metadata solc appends, whose length is encoded in the last
+/// two bytes.
+pub fn strip_metadata(code: &[u8]) -> &[u8] {
+    if code.len() < 2 {
+        return code;
+    }
+
+    let length = u16::from_be_bytes([code[code.len() - 2], code[code.len() - 1]]) as usize;
+    match code.len().checked_sub(length + 2) {
+        Some(end) => &code[..end],
+        None => code,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RUNTIME: [u8; 6] = [0x60, 0x80, 0x60, 0x40, 0x52, 0x00];
+
+    /// Runtime code followed by `metadata` and its length, as solc appends it.
+    fn with_metadata(metadata: &[u8]) -> Vec<u8> {
+        let length = (metadata.len() as u16).to_be_bytes();
+        [&RUNTIME[..], metadata, &length].concat()
+    }
+
+    fn expected(code: Vec<u8>, immutables: Vec<(usize, usize)>) -> ExpectedCode {
+        ExpectedCode {
+            name: "Counter".to_string(),
+            address: Address::ZERO,
+            code: code.into(),
+            immutables,
+        }
+    }
+
+    #[test]
+    fn strips_cbor_metadata() {
+        let code = with_metadata(&[0xa1, 0x64, 0x73, 0x6f, 0x6c, 0x63]);
+        assert_eq!(strip_metadata(&code), RUNTIME);
+
+        // A length past the start of the code is not metadata.
+        let code = [&RUNTIME[..], &[0x01, 0x00]].concat();
+        assert_eq!(strip_metadata(&code), &code[..]);
+        assert_eq!(strip_metadata(&[0x00]), [0x00_u8]);
+        assert!(strip_metadata(&[]).is_empty());
+    }
+
+    #[test]
+    fn compares_deployed_code() {
+        let code = with_metadata(&[0xa1, 0x01]);
+        let expected = expected(code.clone(), vec![]);
+
+        assert_eq!(compare(&expected, &[], false), Status::Missing);
+        assert_eq!(compare(&expected, &code, false), Status::Verified);
+        assert_eq!(compare(&expected, &code[1..], false), Status::Mismatch);
+
+        let mut modified = code.clone();
+        modified[1] = 0xff;
+        assert_eq!(compare(&expected, &modified, false), Status::Mismatch);
+    }
+
+    #[test]
+    fn ignores_immutable_values() {
+        let code = RUNTIME.to_vec();
+        let mut deployed = code.clone();
+        deployed[2..4].copy_from_slice(&[0xde, 0xad]);
+
+        assert_eq!(
+            compare(&expected(code.clone(), vec![]), &deployed, false),
+            Status::Mismatch
+        );
+        assert_eq!(
+            compare(&expected(code, vec![(2, 2)]), &deployed, false),
+            Status::Verified
+        );
+    }
+
+    #[test]
+    fn optionally_ignores_metadata() {
+        let expected = expected(with_metadata(&[0xa1, 0x01]), vec![]);
+        let deployed = with_metadata(&[0xa1, 0x02, 0x03]);
+
+        assert_eq!(compare(&expected, &deployed, false), Status::Mismatch);
+        assert_eq!(compare(&expected, &deployed, true), Status::Verified);
+    }
+}